@@ -0,0 +1,40 @@
+//! Joins the lobbies with the IDs given on the command line and plays random legal moves in them.
+//! `GET /api/v1/lobbies` lists the IDs.
+//!
+//! `cargo run -p bot_sdk --example random_bot -- localhost:8000 <lobby ID>...`
+
+use rand::seq::SliceRandom;
+use uuid::Uuid;
+
+use bot_sdk::common::{Action, Game};
+use bot_sdk::{Bot, Runner};
+
+struct RandomBot;
+
+impl Bot for RandomBot {
+    fn choose_action(&mut self, player: Uuid, state: &Game) -> Option<Action> {
+        let action = state
+            .legal_actions(player)
+            .choose(&mut rand::thread_rng())?
+            .clone();
+
+        Some(action)
+    }
+}
+
+#[async_std::main]
+async fn main() -> Result<(), bot_sdk::Error> {
+    let mut args = std::env::args().skip(1);
+    let server = args
+        .next()
+        .unwrap_or_else(|| String::from("localhost:8000"));
+
+    let mut runner = Runner::register(&server, "random bot", RandomBot).await?;
+
+    for lobby in args {
+        let join = runner.join(&lobby).await?;
+        println!("joined {} as {}", lobby, join.player);
+    }
+
+    runner.run().await
+}
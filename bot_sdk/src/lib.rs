@@ -0,0 +1,218 @@
+//! Write bots that play on the server through the same API as human players.
+//!
+//! Implement [`Bot`], register it with [`Runner::register`], join some lobbies and call
+//! [`Runner::run`]. The runner waits for turns across all joined lobbies and submits whatever
+//! the bot chooses.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_std::task;
+use uuid::Uuid;
+
+use client_sdk::GameClient;
+use common::error::ApiError;
+use common::{Action, BotCredentials, Game, JoinResponse, TurnEvent};
+
+pub use client_sdk::{self, Error};
+pub use common;
+
+/// How long each request for events waits on the server before trying again, in seconds.
+const EVENT_TIMEOUT: u64 = 30;
+
+/// How long to wait before trying again after the server failed, or before asking again for turns
+/// the bot couldn't take.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// The longest wait between tries while the server keeps failing.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+pub trait Bot {
+    /// Pick the next action for `player`, or `None` to pass on the turn. Only called when
+    /// `player` has a legal action.
+    fn choose_action(&mut self, player: Uuid, state: &Game) -> Option<Action>;
+}
+
+/// Drives a [`Bot`] against a server.
+pub struct Runner<B: Bot> {
+    client: GameClient,
+    credentials: BotCredentials,
+    bot: B,
+    /// Turns the bot passed on or had rejected, by lobby and player, with the version of the
+    /// lobby at the time. The server keeps sending them until the game moves on.
+    stalled: HashMap<(String, Uuid), u64>,
+}
+
+impl<B: Bot> Runner<B> {
+    /// Register a new bot under `name` on the server at `server`, e.g. `localhost:8000`.
+    pub async fn register(server: &str, name: &str, bot: B) -> Result<Self, Error> {
+        let credentials = GameClient::new(server).register_bot(name).await?;
+
+        Ok(Self::with_credentials(server, credentials, bot))
+    }
+
+    /// Reuse the credentials from an earlier registration.
+    pub fn with_credentials(server: &str, credentials: BotCredentials, bot: B) -> Self {
+        Self {
+            client: GameClient::new(server),
+            credentials,
+            bot,
+            stalled: HashMap::new(),
+        }
+    }
+
+    /// For anything else the bot wants from the server.
+    pub fn client(&self) -> &GameClient {
+        &self.client
+    }
+
+    pub fn credentials(&self) -> &BotCredentials {
+        &self.credentials
+    }
+
+    pub async fn join(&mut self, lobby: &str) -> Result<JoinResponse, Error> {
+        self.client.bot_join(&self.credentials, lobby).await
+    }
+
+    /// Wait for the lobbies where it's the bot's turn.
+    pub async fn next_turns(&mut self) -> Result<Vec<TurnEvent>, Error> {
+        self.client
+            .bot_events(&self.credentials, EVENT_TIMEOUT)
+            .await
+    }
+
+    /// Let the bot choose an action for `turn` and submit it, returning the new game state.
+    /// `None` if the bot passed.
+    pub async fn take_turn(&mut self, turn: &TurnEvent) -> Result<Option<Game>, Error> {
+        match self.bot.choose_action(turn.player, &turn.game) {
+            Some(action) => self.client.act(&turn.lobby, &action).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Play every turn in the joined lobbies as they come up. Turns the bot passes on or the server
+    /// rejects are printed to stderr and left until their game changes, and requests that fail
+    /// are tried again after a wait. Only returns if the server doesn't accept the bot's
+    /// credentials.
+    pub async fn run(&mut self) -> Result<(), Error> {
+        let mut delay = RETRY_DELAY;
+
+        loop {
+            let turns = match self.next_turns().await {
+                Ok(turns) => turns,
+                Err(e) if is_fatal(&e) => return Err(e),
+                Err(e) => {
+                    back_off(&mut delay, &e).await;
+                    continue;
+                }
+            };
+
+            delay = RETRY_DELAY;
+
+            match self.play(&turns).await {
+                Ok(played) => {
+                    // Everything sent was stalled, and will be sent again straight away.
+                    if !played && !turns.is_empty() {
+                        task::sleep(RETRY_DELAY).await;
+                    }
+                }
+                Err(e) if is_fatal(&e) => return Err(e),
+                Err(e) => back_off(&mut delay, &e).await,
+            }
+        }
+    }
+
+    /// Take whichever of `turns` haven't stalled at their lobby's current version, returning
+    /// whether any were taken. Fails on errors worth trying the turn again for, like a dropped
+    /// connection.
+    async fn play(&mut self, turns: &[TurnEvent]) -> Result<bool, Error> {
+        self.stalled.retain(|(lobby, player), _| {
+            turns
+                .iter()
+                .any(|turn| turn.lobby == *lobby && turn.player == *player)
+        });
+
+        let mut played = false;
+
+        for turn in turns {
+            let key = (turn.lobby.clone(), turn.player);
+            if self.stalled.get(&key) == Some(&turn.version) {
+                continue;
+            }
+
+            match self.take_turn(turn).await {
+                Ok(Some(_)) => played = true,
+                Ok(None) => {
+                    eprintln!("the bot passed on its turn in {}", turn.lobby);
+                    self.stalled.insert(key, turn.version);
+                }
+                Err(Error::Api(e)) if e != ApiError::Unauthorized && e != ApiError::Busy => {
+                    eprintln!(
+                        "the server rejected the bot's turn in {}: {}",
+                        turn.lobby, e
+                    );
+                    self.stalled.insert(key, turn.version);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(played)
+    }
+}
+
+/// Whether `error` will keep happening however many times the bot tries again.
+fn is_fatal(error: &Error) -> bool {
+    matches!(error, Error::Api(ApiError::Unauthorized))
+}
+
+/// Print `error` and wait `delay` before trying again, doubling it for the next failure in a row.
+async fn back_off(delay: &mut Duration, error: &Error) {
+    eprintln!("{}, trying again in {}s", error, delay.as_secs());
+    task::sleep(*delay).await;
+    *delay = (*delay * 2).min(MAX_RETRY_DELAY);
+}
+
+#[cfg(test)]
+struct PassingBot {
+    turns: usize,
+}
+
+#[cfg(test)]
+impl Bot for PassingBot {
+    fn choose_action(&mut self, _player: Uuid, _state: &Game) -> Option<Action> {
+        self.turns += 1;
+        None
+    }
+}
+
+#[test]
+fn test_passed_turns_wait_for_the_game_to_change() {
+    let credentials = BotCredentials {
+        bot: Uuid::new_v4(),
+        api_key: String::new(),
+    };
+    let mut runner =
+        Runner::with_credentials("localhost:8000", credentials, PassingBot { turns: 0 });
+
+    let turn = |version| TurnEvent {
+        lobby: String::from("lobby"),
+        player: Uuid::nil(),
+        game: Game::TicTacToe(Default::default()),
+        version,
+    };
+
+    task::block_on(async {
+        assert!(!runner.play(&[turn(1)]).await.unwrap());
+        assert!(!runner.play(&[turn(1)]).await.unwrap());
+        assert_eq!(runner.bot.turns, 1);
+
+        runner.play(&[turn(2)]).await.unwrap();
+        assert_eq!(runner.bot.turns, 2);
+
+        // Forgotten once the lobby stops coming up, in case its version starts over.
+        runner.play(&[]).await.unwrap();
+        runner.play(&[turn(2)]).await.unwrap();
+        assert_eq!(runner.bot.turns, 3);
+    });
+}
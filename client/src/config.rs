@@ -0,0 +1,180 @@
+//! Settings kept between runs, in `game-client/config.json` under the user's config directory.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// How many servers the Connect screen remembers.
+const MAX_RECENT_SERVERS: usize = 5;
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// Connect to this server on startup instead of asking for one.
+    pub server: Option<String>,
+
+    /// Servers connected to from the Connect screen, most recent first.
+    pub recent_servers: Vec<String>,
+
+    /// One of `Theme::NAMES`. Ignored when `NO_COLOR` is set.
+    pub theme: Option<String>,
+
+    /// Colours for some of the theme's roles, by the names in `Theme::ROLES`. See `theme` for how
+    /// colours are written.
+    pub colors: HashMap<String, String>,
+
+    /// How to draw game boards.
+    pub board: BoardStyle,
+
+    /// Where this was loaded from, and is saved back to.
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardStyle {
+    /// Text when the terminal is too small to draw the board with lines.
+    Auto,
+    /// Lines drawn with braille characters.
+    Canvas,
+    /// Letters in a grid, which also works with screen readers.
+    Text,
+}
+
+impl Default for BoardStyle {
+    fn default() -> Self {
+        BoardStyle::Auto
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/game-client`, falling back to `~/.config`.
+    pub fn dir() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(base.join("game-client"))
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Self::dir()?.join("config.json"))
+    }
+
+    /// Read the config at `path`. A missing or unreadable file gives the defaults, so the client
+    /// always starts.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut config: Config = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+
+        config.path = path;
+        config
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Move `server` to the top of the recent servers.
+    pub fn add_recent_server(&mut self, server: &str) {
+        self.recent_servers.retain(|recent| recent != server);
+        self.recent_servers.insert(0, server.to_owned());
+        self.recent_servers.truncate(MAX_RECENT_SERVERS);
+    }
+}
+
+/// A fresh directory for a test to keep its config in.
+#[cfg(test)]
+fn test_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("game-client-test-{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_save_and_load() {
+    let dir = test_dir("save");
+    let path = dir.join("nested").join("config.json");
+
+    let mut config = Config::load(Some(path.clone()));
+    assert!(config.server.is_none());
+    assert!(config.recent_servers.is_empty());
+
+    config.server = Some(String::from("example.com:8000"));
+    config.theme = Some(String::from("light"));
+    config.add_recent_server("example.com:8000");
+    config.save().unwrap();
+
+    let loaded = Config::load(Some(path.clone()));
+    assert_eq!(loaded.server, config.server);
+    assert_eq!(loaded.recent_servers, ["example.com:8000"]);
+    assert_eq!(loaded.theme, config.theme);
+    assert_eq!(loaded.path, Some(path));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_falls_back_to_defaults() {
+    let dir = test_dir("load");
+    fs::create_dir_all(&dir).unwrap();
+
+    let broken = dir.join("broken.json");
+    fs::write(&broken, "{ \"server\": ").unwrap();
+    assert!(Config::load(Some(broken)).server.is_none());
+
+    let partial = dir.join("partial.json");
+    fs::write(&partial, r#"{ "server": "localhost:8000" }"#).unwrap();
+    let config = Config::load(Some(partial));
+    assert_eq!(config.server.as_deref(), Some("localhost:8000"));
+    assert!(config.recent_servers.is_empty());
+    assert!(config.theme.is_none());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_save_without_path() {
+    let mut config = Config::load(None);
+    config.add_recent_server("localhost:8000");
+
+    assert!(config.save().is_ok());
+}
+
+#[test]
+fn test_recent_servers() {
+    let mut config = Config::default();
+
+    config.add_recent_server("a");
+    config.add_recent_server("b");
+    config.add_recent_server("a");
+    assert_eq!(config.recent_servers, ["a", "b"]);
+
+    for server in &["c", "d", "e", "f"] {
+        config.add_recent_server(server);
+    }
+    assert_eq!(config.recent_servers, ["f", "e", "d", "c", "a"]);
+
+    config.add_recent_server("b");
+    assert_eq!(config.recent_servers.len(), MAX_RECENT_SERVERS);
+    assert_eq!(config.recent_servers[0], "b");
+    assert!(!config.recent_servers.contains(&String::from("a")));
+}
@@ -0,0 +1,507 @@
+//! Which keys do what, from `game-client/keymap.json` next to the config.
+//!
+//! The file picks a preset and overrides the keys of any commands, e.g.
+//!
+//! ```json
+//! {
+//!     "preset": "vim",
+//!     "bindings": {
+//!         "suggest": ["g"],
+//!         "quit": ["q", "Ctrl-q"]
+//!     }
+//! }
+//! ```
+//!
+//! Keys are a single character, one of `Enter`, `Esc`, `Tab`, `Space`, `Backspace`, `Delete`,
+//! `Insert`, `Home`, `End`, `PageUp`, `PageDown`, the arrows `Up`, `Down`, `Left` and `Right`,
+//! `F1` to `F12`, or a character with `Ctrl-` or `Alt-` in front.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use termion::event::Key;
+
+use crate::config::Config;
+
+/// What a key asks for, whichever key it is.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Home,
+    Quit,
+    /// Quits from anywhere, even while typing.
+    Exit,
+    Help,
+    Suggest,
+    /// Also refreshes the lobby list.
+    Retry,
+    Search,
+    Filter,
+    OpenOnly,
+    Reverse,
+    Cell1,
+    Cell2,
+    Cell3,
+    Cell4,
+    Cell5,
+    Cell6,
+    Cell7,
+    Cell8,
+    Cell9,
+}
+
+impl Command {
+    pub const ALL: [Command; 25] = [
+        Command::Up,
+        Command::Down,
+        Command::Left,
+        Command::Right,
+        Command::Confirm,
+        Command::Back,
+        Command::Home,
+        Command::Quit,
+        Command::Exit,
+        Command::Help,
+        Command::Suggest,
+        Command::Retry,
+        Command::Search,
+        Command::Filter,
+        Command::OpenOnly,
+        Command::Reverse,
+        Command::Cell1,
+        Command::Cell2,
+        Command::Cell3,
+        Command::Cell4,
+        Command::Cell5,
+        Command::Cell6,
+        Command::Cell7,
+        Command::Cell8,
+        Command::Cell9,
+    ];
+
+    /// The tic-tac-toe cell this picks, numbered like a number pad: 1 is the bottom left and 9
+    /// the top right.
+    pub fn cell(self) -> Option<u8> {
+        match self {
+            Command::Cell1 => Some(1),
+            Command::Cell2 => Some(2),
+            Command::Cell3 => Some(3),
+            Command::Cell4 => Some(4),
+            Command::Cell5 => Some(5),
+            Command::Cell6 => Some(6),
+            Command::Cell7 => Some(7),
+            Command::Cell8 => Some(8),
+            Command::Cell9 => Some(9),
+            _ => None,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Command::Up => "Move up",
+            Command::Down => "Move down",
+            Command::Left => "Move left, or sort the lobbies by the previous column",
+            Command::Right => "Move right, or sort the lobbies by the next column",
+            Command::Confirm => "Choose the selection, or play it",
+            Command::Back => "Go back",
+            Command::Home => "Go back to the first screen",
+            Command::Quit => "Quit",
+            Command::Exit => "Quit, even while typing",
+            Command::Help => "Show or hide this help",
+            Command::Suggest => "Suggest a rock-paper-scissors move",
+            Command::Retry => "Retry after a network error, or refresh the lobbies",
+            Command::Search => "Search the lobbies by name",
+            Command::Filter => "Show the lobbies for each game in turn, then all of them",
+            Command::OpenOnly => "Show only lobbies with open seats, or all of them",
+            Command::Reverse => "Reverse the order of the lobbies",
+            _ => "Pick a tic-tac-toe cell, laid out like a number pad",
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    /// Arrow keys, Enter and Esc.
+    Default,
+    /// The defaults, plus h, j, k and l to move.
+    Vim,
+    /// The defaults, plus w, a, s and d to move, with suggestions moved to e.
+    Wasd,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Preset::Default
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct KeymapFile {
+    preset: Preset,
+    bindings: HashMap<Command, Vec<String>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    keys: HashMap<Command, Vec<Key>>,
+    commands: HashMap<Key, Command>,
+}
+
+impl Keymap {
+    pub fn default_path() -> Option<PathBuf> {
+        Some(Config::dir()?.join("keymap.json"))
+    }
+
+    pub fn preset(preset: Preset) -> Self {
+        let mut keys: HashMap<Command, Vec<Key>> = HashMap::new();
+        let mut bind = |command, bound: &[Key]| {
+            keys.entry(command).or_default().extend_from_slice(bound);
+        };
+
+        bind(Command::Up, &[Key::Up]);
+        bind(Command::Down, &[Key::Down]);
+        bind(Command::Left, &[Key::Left]);
+        bind(Command::Right, &[Key::Right]);
+        bind(Command::Confirm, &[Key::Char('\n')]);
+        bind(Command::Back, &[Key::Esc]);
+        bind(Command::Home, &[Key::Home]);
+        bind(Command::Quit, &[Key::Char('q')]);
+        bind(Command::Exit, &[Key::Ctrl('c')]);
+        bind(Command::Help, &[Key::Char('?'), Key::F(1)]);
+        bind(Command::Retry, &[Key::Char('r')]);
+        bind(Command::Search, &[Key::Char('/')]);
+        bind(Command::Filter, &[Key::Char('f')]);
+        bind(Command::OpenOnly, &[Key::Char('o')]);
+        bind(Command::Reverse, &[Key::Char('v')]);
+
+        for (command, digit) in Command::ALL.iter().filter_map(|c| Some((*c, c.cell()?))) {
+            bind(command, &[Key::Char((b'0' + digit) as char)]);
+        }
+
+        match preset {
+            Preset::Default => bind(Command::Suggest, &[Key::Char('s')]),
+            Preset::Vim => {
+                bind(Command::Suggest, &[Key::Char('s')]);
+                bind(Command::Up, &[Key::Char('k')]);
+                bind(Command::Down, &[Key::Char('j')]);
+                bind(Command::Left, &[Key::Char('h')]);
+                bind(Command::Right, &[Key::Char('l')]);
+            }
+            Preset::Wasd => {
+                bind(Command::Suggest, &[Key::Char('e')]);
+                bind(Command::Up, &[Key::Char('w')]);
+                bind(Command::Down, &[Key::Char('s')]);
+                bind(Command::Left, &[Key::Char('a')]);
+                bind(Command::Right, &[Key::Char('d')]);
+            }
+        }
+
+        Self::from_keys(keys)
+    }
+
+    /// Read the keymap at `path`, or the default one if there's no file. Unlike the config, a
+    /// broken keymap is an error, since guessing could leave the player without a way to quit.
+    pub fn load(path: Option<PathBuf>) -> Result<Self, String> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(Self::preset(Preset::Default)),
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::preset(Preset::Default))
+            }
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+
+        let file: KeymapFile = serde_json::from_str(&text)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+
+        let mut keys = Self::preset(file.preset).keys;
+
+        for (command, names) in file.bindings {
+            let bound = names
+                .iter()
+                .map(|name| {
+                    parse_key(name).ok_or_else(|| {
+                        format!(
+                            "Unknown key {:?} for {:?} in {}",
+                            name,
+                            command,
+                            path.display()
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            keys.insert(command, bound);
+        }
+
+        Ok(Self::from_keys(keys))
+    }
+
+    /// When a key is bound to several commands, the last in `Command::ALL` wins.
+    fn from_keys(keys: HashMap<Command, Vec<Key>>) -> Self {
+        let mut commands = HashMap::new();
+
+        for command in Command::ALL.iter() {
+            for key in keys.get(command).into_iter().flatten() {
+                commands.insert(*key, *command);
+            }
+        }
+
+        Self { keys, commands }
+    }
+
+    pub fn command(&self, key: Key) -> Option<Command> {
+        self.commands.get(&key).copied()
+    }
+
+    /// The first key for `command`, for hints like "press Esc to go back".
+    pub fn key(&self, command: Command) -> String {
+        match self.keys.get(&command).and_then(|keys| keys.first()) {
+            Some(key) => key_name(*key),
+            None => String::from("(unbound)"),
+        }
+    }
+
+    /// What each command does and the keys for it, for the help screen.
+    pub fn help(&self) -> Vec<(&'static str, String)> {
+        let names = |commands: &[Command]| {
+            commands
+                .iter()
+                .flat_map(|command| self.keys.get(command).into_iter().flatten())
+                .map(|key| key_name(*key))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let (cells, others): (Vec<Command>, Vec<Command>) = Command::ALL
+            .iter()
+            .copied()
+            .partition(|command| command.cell().is_some());
+
+        let mut help: Vec<_> = others
+            .iter()
+            .map(|command| (command.description(), names(&[*command])))
+            .collect();
+
+        help.push((cells[0].description(), names(&cells)));
+        help
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(Preset::default())
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+
+    if let Some(rest) = strip_prefix(name, "Ctrl-") {
+        return single(rest).map(Key::Ctrl);
+    }
+
+    if let Some(rest) = strip_prefix(name, "Alt-") {
+        return single(rest).map(Key::Alt);
+    }
+
+    if let Some(number) = strip_prefix(name, "F").and_then(|rest| rest.parse().ok()) {
+        return if (1..=12).contains(&number) {
+            Some(Key::F(number))
+        } else {
+            None
+        };
+    }
+
+    Some(match name {
+        "Enter" => Key::Char('\n'),
+        "Esc" => Key::Esc,
+        "Tab" => Key::Char('\t'),
+        "Space" => Key::Char(' '),
+        "Backspace" => Key::Backspace,
+        "Delete" => Key::Delete,
+        "Insert" => Key::Insert,
+        "Home" => Key::Home,
+        "End" => Key::End,
+        "PageUp" => Key::PageUp,
+        "PageDown" => Key::PageDown,
+        "Up" => Key::Up,
+        "Down" => Key::Down,
+        "Left" => Key::Left,
+        "Right" => Key::Right,
+        _ => Key::Char(single(name)?),
+    })
+}
+
+fn strip_prefix<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    if name.starts_with(prefix) && name.len() > prefix.len() {
+        Some(&name[prefix.len()..])
+    } else {
+        None
+    }
+}
+
+/// How a key is written in the keymap file.
+pub fn key_name(key: Key) -> String {
+    match key {
+        Key::Char('\n') => String::from("Enter"),
+        Key::Char('\t') => String::from("Tab"),
+        Key::Char(' ') => String::from("Space"),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        Key::Esc => String::from("Esc"),
+        Key::Backspace => String::from("Backspace"),
+        Key::Delete => String::from("Delete"),
+        Key::Insert => String::from("Insert"),
+        Key::Home => String::from("Home"),
+        Key::End => String::from("End"),
+        Key::PageUp => String::from("PageUp"),
+        Key::PageDown => String::from("PageDown"),
+        Key::Up => String::from("Up"),
+        Key::Down => String::from("Down"),
+        Key::Left => String::from("Left"),
+        Key::Right => String::from("Right"),
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+fn load_text(name: &str, text: &str) -> Result<Keymap, String> {
+    let path = std::env::temp_dir().join(format!("keymap-test-{}.json", name));
+    fs::write(&path, text).unwrap();
+
+    let keymap = Keymap::load(Some(path.clone()));
+    fs::remove_file(path).unwrap();
+    keymap
+}
+
+#[test]
+fn test_parse_named_keys() {
+    assert_eq!(parse_key("Enter"), Some(Key::Char('\n')));
+    assert_eq!(parse_key("Space"), Some(Key::Char(' ')));
+    assert_eq!(parse_key("Esc"), Some(Key::Esc));
+    assert_eq!(parse_key("PageDown"), Some(Key::PageDown));
+    assert_eq!(parse_key("F1"), Some(Key::F(1)));
+    assert_eq!(parse_key("F12"), Some(Key::F(12)));
+    assert_eq!(parse_key("F"), Some(Key::Char('F')));
+    assert_eq!(parse_key("q"), Some(Key::Char('q')));
+}
+
+#[test]
+fn test_parse_modified_keys() {
+    assert_eq!(parse_key("Ctrl-q"), Some(Key::Ctrl('q')));
+    assert_eq!(parse_key("Alt-x"), Some(Key::Alt('x')));
+    assert_eq!(parse_key("Ctrl--"), Some(Key::Ctrl('-')));
+}
+
+#[test]
+fn test_parse_invalid_keys() {
+    for name in &[
+        "", "Ctrl-", "Ctrl-ab", "Alt-", "F0", "F13", "Enterr", "qq", "ctrl-q",
+    ] {
+        assert_eq!(parse_key(name), None, "{:?}", name);
+    }
+}
+
+#[test]
+fn test_key_names_round_trip() {
+    let keymap = Keymap::default();
+
+    for keys in keymap.keys.values() {
+        for key in keys {
+            assert_eq!(parse_key(&key_name(*key)), Some(*key));
+        }
+    }
+}
+
+#[test]
+fn test_presets() {
+    let default = Keymap::preset(Preset::Default);
+    assert_eq!(default.command(Key::Up), Some(Command::Up));
+    assert_eq!(default.command(Key::Char('s')), Some(Command::Suggest));
+    assert_eq!(default.command(Key::Char('k')), None);
+    assert_eq!(default.command(Key::Char('5')), Some(Command::Cell5));
+
+    let vim = Keymap::preset(Preset::Vim);
+    assert_eq!(vim.command(Key::Char('k')), Some(Command::Up));
+    assert_eq!(vim.command(Key::Up), Some(Command::Up));
+    assert_eq!(vim.key(Command::Up), "Up");
+
+    let wasd = Keymap::preset(Preset::Wasd);
+    assert_eq!(wasd.command(Key::Char('s')), Some(Command::Down));
+    assert_eq!(wasd.command(Key::Char('e')), Some(Command::Suggest));
+}
+
+#[test]
+fn test_load() {
+    assert!(Keymap::load(None).is_ok());
+
+    let keymap = load_text(
+        "load",
+        r#"{ "preset": "vim", "bindings": { "quit": ["x", "Ctrl-q"] } }"#,
+    )
+    .unwrap();
+
+    assert_eq!(keymap.command(Key::Char('x')), Some(Command::Quit));
+    assert_eq!(keymap.command(Key::Ctrl('q')), Some(Command::Quit));
+    assert_eq!(keymap.command(Key::Char('j')), Some(Command::Down));
+    // Rebinding a command replaces the preset's keys for it.
+    assert_eq!(keymap.command(Key::Char('q')), None);
+    assert_eq!(keymap.key(Command::Quit), "x");
+}
+
+#[test]
+fn test_load_errors() {
+    let missing = std::env::temp_dir().join("keymap-test-missing.json");
+    assert!(Keymap::load(Some(missing)).is_ok());
+
+    assert!(load_text("broken", "{ \"preset\": ").is_err());
+    assert!(load_text("preset", r#"{ "preset": "emacs" }"#).is_err());
+
+    let error = load_text("key", r#"{ "bindings": { "quit": ["Hyper-q"] } }"#)
+        .err()
+        .unwrap();
+    assert!(error.contains("Hyper-q"), "{}", error);
+}
+
+#[test]
+fn test_conflicting_bindings() {
+    // Quit comes before Suggest in `Command::ALL`, so Suggest keeps s.
+    let keymap = load_text("conflict", r#"{ "bindings": { "quit": ["s"] } }"#).unwrap();
+
+    assert_eq!(keymap.command(Key::Char('s')), Some(Command::Suggest));
+    assert_eq!(keymap.key(Command::Quit), "s");
+
+    // Moving Suggest away frees the key up.
+    let keymap = load_text(
+        "moved",
+        r#"{ "bindings": { "quit": ["s"], "suggest": ["g"] } }"#,
+    )
+    .unwrap();
+
+    assert_eq!(keymap.command(Key::Char('s')), Some(Command::Quit));
+    assert_eq!(keymap.command(Key::Char('g')), Some(Command::Suggest));
+}
@@ -1,9 +1,14 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+mod config;
+mod keymap;
+mod overlay;
+mod theme;
 mod util;
 
 use std::io;
+use std::path::PathBuf;
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
@@ -16,6 +21,14 @@ use tui::widgets::{Block, Borders, Row, Table, Widget};
 use tui::Terminal;
 
 use crate::util::event::{Event, Events};
+use crate::util::log::Log;
+use crate::util::network::Network;
+
+use client_sdk::GameClient;
+
+use config::Config;
+use keymap::Keymap;
+use theme::Theme;
 
 mod state;
 use state::StateManager;
@@ -28,40 +41,117 @@ use states::MainMenu;
 use states::TicTacToe;
 use std::panic::{self, PanicInfo};
 
+const USAGE: &str = "\
+Usage: client [--server <address>] [--config <path>] [--keymap <path>] [--log <path>]
+
+    -s, --server <address>  Connect to this server, e.g. localhost:8000, instead of asking
+    -c, --config <path>     Read settings from here instead of the default config file
+    -k, --keymap <path>     Read key bindings from here instead of the default keymap file
+    -l, --log <path>        Write the details of errors here instead of the default log file";
+
+#[derive(Default)]
+struct Args {
+    server: Option<String>,
+    config: Option<PathBuf>,
+    keymap: Option<PathBuf>,
+    log: Option<PathBuf>,
+}
+
+impl Args {
+    fn parse() -> Result<Self, String> {
+        let mut args = Self::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .ok_or_else(|| format!("{} needs a value\n\n{}", arg, USAGE))
+            };
+
+            match arg.as_str() {
+                "-s" | "--server" => args.server = Some(value()?),
+                "-c" | "--config" => args.config = Some(PathBuf::from(value()?)),
+                "-k" | "--keymap" => args.keymap = Some(PathBuf::from(value()?)),
+                "-l" | "--log" => args.log = Some(PathBuf::from(value()?)),
+                "-h" | "--help" => return Err(String::from(USAGE)),
+                _ => return Err(format!("unknown argument {}\n\n{}", arg, USAGE)),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
 #[async_std::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    panic::set_hook(Box::new(|info| {
-        panic_hook(info);
-    }));
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            return Ok(());
+        }
+    };
+
+    let config = Config::load(args.config.or_else(Config::default_path));
+
+    let keymap = match Keymap::load(args.keymap.or_else(Keymap::default_path)) {
+        Ok(keymap) => keymap,
+        Err(message) => {
+            eprintln!("{}", message);
+            return Ok(());
+        }
+    };
+
+    let log = Log::new(args.log.or_else(Log::default_path));
+
+    let theme = Theme::load(config.theme.as_deref(), &config.colors).unwrap_or_else(|e| {
+        log.write(&e);
+        Theme::default()
+    });
+
+    {
+        let log = log.clone();
+        panic::set_hook(Box::new(move |info| {
+            panic_hook(info, &log);
+        }));
+    }
 
     // Terminal initialization
-    let stdout = io::stdout().into_raw_mode().unwrap();
+    let stdout = io::stdout().into_raw_mode()?;
     let stdout = MouseTerminal::from(stdout);
     let stdout = AlternateScreen::from(stdout);
     let backend = TermionBackend::new(stdout);
-    let mut terminal = Terminal::new(backend).unwrap();
-    terminal.hide_cursor().unwrap();
+    let mut terminal = Terminal::new(backend)?;
+    terminal.hide_cursor()?;
 
     let events = Events::new();
 
-    let mut state_manager = StateManager::new();
+    let mut state_manager = StateManager::new(events.sender(), log.clone(), keymap, theme);
 
     //state_manager.push(Box::new(GameBrowser::new())).await;
-    state_manager
-        .push(Box::new(MainMenu::new("localhost:8000")))
-        .await;
+    match args.server.or_else(|| config.server.clone()) {
+        Some(server) => {
+            let network = Network::new(GameClient::new(&server), events.sender());
+            state_manager
+                .push(Box::new(MainMenu::new(network, config.board)))
+                .await
+        }
+        None => {
+            state_manager
+                .push(Box::new(Connect::new(config, events.sender())))
+                .await
+        }
+    }
 
     // Input
-    loop {
-        state_manager.render(&mut terminal);
+    while state_manager.is_running() {
+        if let Err(e) = state_manager.render(&mut terminal) {
+            log.write(&format!("Couldn't draw to the terminal: {}", e));
+            state_manager.quit().await;
+            return Err(e.into());
+        }
 
         if let Ok(event) = events.next() {
-            if let Event::Input(key) = event {
-                if let Key::Char('q') = key {
-                    break;
-                }
-            }
-
             state_manager.update().await;
 
             state_manager.on_event(event).await;
@@ -71,7 +161,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn panic_hook(info: &PanicInfo<'_>) {
+fn panic_hook(info: &PanicInfo<'_>, log: &Log) {
     let location = info.location().unwrap(); // The current implementation always returns Some
 
     let msg = match info.payload().downcast_ref::<&'static str>() {
@@ -81,6 +171,7 @@ fn panic_hook(info: &PanicInfo<'_>) {
             None => "Box<Any>",
         },
     };
+    log.write(&format!("panicked at '{}', {}", msg, location));
     println!(
         "{}thread '<unnamed>' panicked at '{}', {}\r",
         termion::screen::ToMainScreen,
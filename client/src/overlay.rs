@@ -0,0 +1,133 @@
+use std::io;
+
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Color, Style};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, Text, Widget};
+use tui::Terminal;
+
+use crate::keymap::{Command, Keymap};
+use crate::state::Backend;
+use crate::theme::Theme;
+use crate::util::log::Log;
+use crate::util::network::Fault;
+
+/// Covers the screen while requests can't get through to the server, until the player picks
+/// whether to retry them or give up on them.
+pub struct ErrorOverlay {
+    faults: Vec<Fault>,
+}
+
+impl ErrorOverlay {
+    pub fn new(fault: Fault) -> Self {
+        Self {
+            faults: vec![fault],
+        }
+    }
+
+    /// Requests failing at the same time are retried or given up on together.
+    pub fn add(&mut self, fault: Fault) {
+        self.faults.push(fault);
+    }
+
+    pub fn into_faults(self) -> Vec<Fault> {
+        self.faults
+    }
+
+    pub fn render(
+        &self,
+        terminal: &mut Terminal<Backend>,
+        log: &Log,
+        keymap: &Keymap,
+        theme: &Theme,
+    ) -> io::Result<()> {
+        let mut text = vec![];
+
+        for fault in &self.faults {
+            text.push(Text::raw(format!("{}\n", fault.error)));
+        }
+
+        if let Some(path) = log.path() {
+            text.push(Text::raw(format!(
+                "\nThe details are in {}\n",
+                path.display()
+            )));
+        }
+
+        text.push(Text::styled(
+            format!(
+                "\nPress {} to retry or {} to give up.",
+                keymap.key(Command::Retry),
+                keymap.key(Command::Back)
+            ),
+            theme.text,
+        ));
+
+        terminal.draw(|mut f| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(25),
+                        Constraint::Percentage(50),
+                        Constraint::Percentage(25),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let area = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(70),
+                        Constraint::Percentage(15),
+                    ]
+                    .as_ref(),
+                )
+                .split(rows[1])[1];
+
+            Paragraph::new(text.iter())
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Something went wrong")
+                        .title_style(theme.error),
+                )
+                .style(theme.error)
+                .alignment(Alignment::Center)
+                .wrap(true)
+                .render(&mut f, area);
+        })
+    }
+}
+
+/// Lists what each key does, over the whole screen.
+pub fn render_help(
+    terminal: &mut Terminal<Backend>,
+    keymap: &Keymap,
+    theme: &Theme,
+) -> io::Result<()> {
+    let help = keymap.help();
+
+    terminal.draw(|mut f| {
+        let area = Layout::default()
+            .constraints([Constraint::Percentage(100)].as_ref())
+            .margin(1)
+            .split(f.size())[0];
+
+        let title = format!("Key bindings ({} to close)", keymap.key(Command::Back));
+
+        let rows = help.iter().map(|(description, keys)| {
+            Row::Data(vec![keys.clone(), description.to_string()].into_iter())
+        });
+
+        Table::new(["Keys", "Does"].iter(), rows)
+            .header_style(theme.status)
+            .style(theme.text)
+            .block(Block::default().borders(Borders::ALL).title(&title))
+            .widths(&[Constraint::Percentage(30), Constraint::Percentage(70)])
+            .column_spacing(2)
+            .render(&mut f, area);
+    })
+}
@@ -2,48 +2,137 @@ use tui::backend::TermionBackend;
 use tui::terminal::Frame;
 use tui::Terminal;
 
-use std::io::Stdout;
+use std::io::{self, Stdout};
+use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::RawTerminal;
 use termion::screen::AlternateScreen;
 
-use crate::util::event::{Event, Events};
+use crate::keymap::{Command, Keymap};
+use crate::overlay::{self, ErrorOverlay};
+use crate::theme::Theme;
+use crate::util::event::{Event, EventSender, Events};
+use crate::util::log::Log;
 use std::io::Write;
 
 pub type Backend = TermionBackend<AlternateScreen<MouseTerminal<RawTerminal<Stdout>>>>;
 
+/// What a state wants to happen to the stack of screens after handling an event.
 pub enum Action {
     None,
     PushState(Box<dyn State>),
+
+    /// Go back to the previous screen. Does nothing on the first screen.
+    Pop,
+
+    /// Swap the current screen for another, so going back skips it.
+    Replace(Box<dyn State>),
+
+    /// Go back to the first screen.
+    PopToRoot,
+
+    Quit,
 }
 
 pub struct StateManager {
     states: Vec<Box<dyn State>>,
+    events: EventSender,
+    running: bool,
+    overlay: Option<ErrorOverlay>,
+    log: Log,
+    keymap: Keymap,
+    theme: Theme,
+    /// Whether the key bindings are being shown over the current state.
+    help: bool,
 }
 
 impl StateManager {
-    pub fn new() -> Self {
-        Self { states: vec![] }
+    pub fn new(events: EventSender, log: Log, keymap: Keymap, theme: Theme) -> Self {
+        Self {
+            states: vec![],
+            events,
+            running: true,
+            overlay: None,
+            log,
+            keymap,
+            theme,
+            help: false,
+        }
+    }
+
+    /// False once a state has asked to quit.
+    pub fn is_running(&self) -> bool {
+        self.running
     }
 
     pub async fn push(&mut self, state: Box<dyn State>) {
         self.states.push(state);
+        let events = self.events.clone();
         if let Some(state) = self.current() {
-            state.on_enter().await;
+            state.on_enter(events).await;
         }
     }
 
-    pub fn pop(&mut self) {
-        self.states.pop();
+    pub async fn pop(&mut self) {
+        if self.states.len() > 1 {
+            self.exit_current().await;
+            self.resume().await;
+        }
+    }
+
+    pub async fn replace(&mut self, state: Box<dyn State>) {
+        self.exit_current().await;
+        self.push(state).await;
+    }
+
+    pub async fn pop_to_root(&mut self) {
+        if self.states.len() > 1 {
+            while self.states.len() > 1 {
+                self.exit_current().await;
+            }
+
+            self.resume().await;
+        }
+    }
+
+    /// Exit every state, top first, and stop running.
+    pub async fn quit(&mut self) {
+        while !self.states.is_empty() {
+            self.exit_current().await;
+        }
+
+        self.running = false;
+    }
+
+    async fn exit_current(&mut self) {
+        if let Some(mut state) = self.states.pop() {
+            state.on_exit().await;
+        }
+    }
+
+    async fn resume(&mut self) {
+        let events = self.events.clone();
+        if let Some(state) = self.current() {
+            state.on_resume(events).await;
+        }
     }
 
     pub fn current(&mut self) -> Option<&mut Box<dyn State>> {
         self.states.last_mut()
     }
 
-    pub fn render(&mut self, terminal: &mut Terminal<Backend>) {
-        if let Some(state) = self.current() {
-            state.render(terminal);
+    pub fn render(&mut self, terminal: &mut Terminal<Backend>) -> io::Result<()> {
+        if let Some(overlay) = &self.overlay {
+            return overlay.render(terminal, &self.log, &self.keymap, &self.theme);
+        }
+
+        if self.help {
+            return overlay::render_help(terminal, &self.keymap, &self.theme);
+        }
+
+        match self.states.last_mut() {
+            Some(state) => state.render(terminal, &self.theme),
+            None => Ok(()),
         }
     }
 
@@ -53,13 +142,86 @@ impl StateManager {
         }
     }
 
+    /// Keys become the commands they're bound to, unless they're being typed. Requests that
+    /// couldn't reach the server are held in the overlay, which takes the input until the player
+    /// retries them or goes back, and the help takes it until it's closed. Everything else goes
+    /// to the current state.
     pub async fn on_event(&mut self, event: Event) {
+        let event = match event {
+            Event::Input(key) => self.translate(key),
+            event => event,
+        };
+
+        match event {
+            Event::Command(Command::Exit) => self.quit().await,
+            Event::Fault(fault) => {
+                self.log.write(&fault.details());
+
+                match &mut self.overlay {
+                    Some(overlay) => overlay.add(fault),
+                    None => self.overlay = Some(ErrorOverlay::new(fault)),
+                }
+            }
+            Event::Command(command) if self.overlay.is_some() => match command {
+                Command::Retry | Command::Confirm => self.retry(),
+                Command::Back => self.give_up().await,
+                _ => {}
+            },
+            Event::Command(command) if self.help => match command {
+                Command::Help | Command::Back | Command::Confirm => self.help = false,
+                _ => {}
+            },
+            Event::Command(Command::Help) => self.help = true,
+            Event::Input(_) | Event::Mouse(_) if self.overlay.is_some() || self.help => {}
+            event => self.dispatch(event).await,
+        }
+    }
+
+    /// Printable keys stay text while the current state is taking it.
+    fn translate(&self, key: Key) -> Event {
+        let typing = match key {
+            Key::Char(c) => !c.is_control(),
+            _ => false,
+        };
+
+        if typing && self.states.last().map_or(false, |state| state.takes_text()) {
+            return Event::Input(key);
+        }
+
+        match self.keymap.command(key) {
+            Some(command) => Event::Command(command),
+            None => Event::Input(key),
+        }
+    }
+
+    fn retry(&mut self) {
+        if let Some(overlay) = self.overlay.take() {
+            for fault in overlay.into_faults() {
+                fault.retry();
+            }
+        }
+    }
+
+    /// Let the state under the overlay know its requests failed, for it to show.
+    async fn give_up(&mut self) {
+        if let Some(overlay) = self.overlay.take() {
+            for fault in overlay.into_faults() {
+                self.dispatch(fault.into_failure()).await;
+            }
+        }
+    }
+
+    async fn dispatch(&mut self, event: Event) {
         if let Some(state) = self.current() {
             match state.on_event(event).await {
                 Action::None => {}
                 Action::PushState(new_state) => {
                     self.push(new_state).await;
                 }
+                Action::Pop => self.pop().await,
+                Action::Replace(new_state) => self.replace(new_state).await,
+                Action::PopToRoot => self.pop_to_root().await,
+                Action::Quit => self.quit().await,
             }
         }
     }
@@ -68,12 +230,25 @@ impl StateManager {
 use async_trait::async_trait;
 
 #[async_trait]
-pub trait State {
-    fn render(&mut self, terminal: &mut Terminal<Backend>);
+pub trait State: Send {
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()>;
 
     async fn on_event(&mut self, event: Event) -> Action;
 
-    async fn on_enter(&mut self);
+    /// `events` feeds the main event loop, e.g. for connections pushing updates from the server.
+    async fn on_enter(&mut self, events: EventSender);
 
     async fn on_update(&mut self);
+
+    /// Called when the state leaves the stack, e.g. to close its connections.
+    async fn on_exit(&mut self) {}
+
+    /// Called when the state is back on top after the ones above it were popped.
+    async fn on_resume(&mut self, _events: EventSender) {}
+
+    /// Whether printable keys should come as `Event::Input` for typing, rather than as the
+    /// commands they're bound to.
+    fn takes_text(&self) -> bool {
+        false
+    }
 }
@@ -4,7 +4,7 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
@@ -13,27 +13,92 @@ use termion::screen::AlternateScreen;
 use tui::backend::TermionBackend;
 use tui::layout::{Constraint, Direction, Layout};
 use tui::style::{Color, Modifier, Style};
-use tui::widgets::{Block, Borders, List, Paragraph, Row, Table, Text, Widget};
+use tui::widgets::{Block, Borders, List, Paragraph, Row, SelectableList, Table, Text, Widget};
 use unicode_width::UnicodeWidthStr;
 
 use std::io::{self, Write};
 
 use termion::cursor::Goto;
 
+use client_sdk::GameClient;
+
+use crate::config::Config;
+use crate::keymap::Command;
+use crate::states::MainMenu;
+use crate::theme::Theme;
+use crate::util::network::{self, Network, Request, RequestId};
+
 pub struct Connect {
-    address: String,
     /// Current value of the input box
     input: String,
-    /// History of recorded messages
-    messages: Vec<String>,
+    /// Which of the recent servers is in the input box, if any
+    selected: Option<usize>,
+    /// Why the last attempt to connect failed
+    error: Option<String>,
+    /// The version check in flight, with the address it's for
+    connecting: Option<(RequestId, String, Network)>,
+
+    config: Config,
+    events: EventSender,
 }
 
 impl Connect {
-    pub fn new() -> Self {
+    pub fn new(config: Config, events: EventSender) -> Self {
+        let input = config.recent_servers.first().cloned().unwrap_or_default();
+        let selected = if input.is_empty() { None } else { Some(0) };
+
         Self {
-            address: String::new(),
-            input: String::new(),
-            messages: Vec::new(),
+            input,
+            selected,
+            error: None,
+            connecting: None,
+            config,
+            events,
+        }
+    }
+
+    fn select(&mut self, selected: usize) {
+        self.selected = Some(selected);
+        self.input = self.config.recent_servers[selected].clone();
+        self.error = None;
+    }
+
+    /// Check the server in the input box is there and speaks our protocol before going on to it.
+    fn connect(&mut self) {
+        if self.input.trim().is_empty() || self.connecting.is_some() {
+            return;
+        }
+
+        // Remember the address the way the client uses it, without a scheme or trailing slash.
+        let client = GameClient::new(&self.input);
+        let address = client.server().to_owned();
+        let network = Network::new(client, self.events.clone());
+        let id = network.send(Request::Version);
+
+        self.error = None;
+        self.connecting = Some((id, address, network));
+    }
+
+    fn connected(&mut self) -> Action {
+        let (address, network) = match self.connecting.take() {
+            Some((_, address, network)) => (address, network),
+            None => return Action::None,
+        };
+
+        self.config.add_recent_server(&address);
+        self.selected = Some(0);
+
+        if let Err(e) = self.config.save() {
+            self.error = Some(format!("Couldn't save the recent servers: {}", e));
+        }
+
+        Action::PushState(Box::new(MainMenu::new(network, self.config.board)))
+    }
+
+    fn is_connecting(&self, id: RequestId) -> bool {
+        match &self.connecting {
+            Some((connecting, _, _)) => *connecting == id,
+            None => false,
         }
     }
 }
@@ -44,65 +109,118 @@ use async_trait::async_trait;
 impl State for Connect {
     async fn on_update(&mut self) {}
 
-    async fn on_enter(&mut self) {}
-
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
-        terminal
-            .draw(|mut f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .margin(2)
-                    .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
-                    .split(f.size());
-
-                Paragraph::new(
-                    [Text::raw(String::from(
-                        "Please enter the matchmaking servers IP Address.",
-                    ))]
-                    .iter(),
+    async fn on_enter(&mut self, _events: EventSender) {}
+
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
+        terminal.draw(|mut f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(2)
+                .constraints(
+                    [
+                        Constraint::Length(3),
+                        Constraint::Length(2),
+                        Constraint::Min(1),
+                    ]
+                    .as_ref(),
                 )
-                .style(Style::default().fg(Color::Blue))
+                .split(f.size());
+
+            let (message, style) = match (&self.connecting, &self.error) {
+                (Some((_, address, _)), _) => (
+                    format!("Connecting to {} {}", address, network::spinner()),
+                    theme.busy,
+                ),
+                (None, Some(error)) => (error.clone(), theme.error),
+                (None, None) => (
+                    String::from(
+                        "Please enter the matchmaking server's address, e.g. localhost:8000.",
+                    ),
+                    theme.status,
+                ),
+            };
+
+            Paragraph::new([Text::raw(message)].iter())
+                .style(style)
+                .wrap(true)
                 .render(&mut f, chunks[1]);
 
-                Paragraph::new([Text::raw(&self.input)].iter())
-                    .style(Style::default().fg(Color::Green))
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Server IP Address"),
-                    )
-                    .render(&mut f, chunks[0]);
-            })
-            .unwrap();
+            Paragraph::new([Text::raw(&self.input)].iter())
+                .style(theme.text)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme.focus)
+                        .title("Server Address"),
+                )
+                .render(&mut f, chunks[0]);
+
+            SelectableList::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Recent Servers (Up/Down to pick)"),
+                )
+                .items(&self.config.recent_servers)
+                .select(self.selected)
+                .style(theme.text)
+                .highlight_style(theme.selection)
+                .highlight_symbol(">")
+                .render(&mut f, chunks[2]);
+        })?;
 
         // Put the cursor back inside the input box
         write!(
             terminal.backend_mut(),
             "{}",
             Goto(4 + self.input.width() as u16, 4)
-        )
-        .unwrap();
+        )?;
         // stdout is buffered, flush it to see the effect immediately when hitting backspace
-        io::stdout().flush().ok();
+        io::stdout().flush()
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(input) => match input {
-                Key::Char('\n') => {
-                    self.messages.push(self.input.drain(..).collect());
+            Event::Input(Key::Char(c)) if !c.is_control() => {
+                self.input.push(c);
+                self.selected = None;
+                self.error = None;
+            }
+            Event::Input(Key::Backspace) => {
+                self.input.pop();
+                self.selected = None;
+            }
+            Event::Command(command) => match command {
+                Command::Back => return Action::Pop,
+                Command::Confirm => self.connect(),
+                Command::Down if !self.config.recent_servers.is_empty() => {
+                    let last = self.config.recent_servers.len() - 1;
+                    self.select(match self.selected {
+                        Some(selected) if selected < last => selected + 1,
+                        _ => 0,
+                    });
                 }
-                Key::Char(c) => {
-                    self.input.push(c);
-                }
-                Key::Backspace => {
-                    self.input.pop();
+                Command::Up if !self.config.recent_servers.is_empty() => {
+                    let last = self.config.recent_servers.len() - 1;
+                    self.select(match self.selected {
+                        Some(selected) if selected > 0 => selected - 1,
+                        _ => last,
+                    });
                 }
                 _ => {}
             },
+            Event::Response(id, _) if self.is_connecting(id) => return self.connected(),
+            Event::Failure(id, error) if self.is_connecting(id) => {
+                self.connecting = None;
+                self.error = Some(error.to_string());
+            }
             _ => {}
         }
 
         Action::None
     }
+
+    fn takes_text(&self) -> bool {
+        true
+    }
 }
@@ -4,7 +4,7 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
@@ -15,21 +15,47 @@ use tui::layout::{Constraint, Layout};
 use tui::style::{Color, Modifier, Style};
 use tui::widgets::{Block, Borders, Paragraph, Row, Table, Tabs, Text, Widget};
 
-use common::{GameType, Lobby};
+use std::io;
+
+use common::{BotDifficulty, CreateLobbyRequest, GameType, Lobby};
+
+use crate::config::BoardStyle;
+use crate::keymap::Command;
+use crate::states;
+use crate::theme::Theme;
+use crate::util::network::{self, Network, Pending, Request, Response};
 
 const SELECTION_MAX: usize = 3;
 
+const OPPONENTS: [&str; 4] = ["Another Player", "Easy Bot", "Medium Bot", "Hard Bot"];
+
 pub struct CreateGame {
+    network: Network,
+    /// How the game screen draws the board.
+    board: BoardStyle,
+    pending: Pending,
+    /// Why creating the game failed
+    error: Option<String>,
+
     lobby_name: String,
+    /// The lobby's ID once the server has created it
+    created: Option<String>,
     game_type: usize,
+    opponent: usize,
     selected: usize,
 }
 
 impl CreateGame {
-    pub fn new() -> Self {
+    pub fn new(network: Network, board: BoardStyle) -> Self {
         Self {
+            network,
+            board,
+            pending: Pending::default(),
+            error: None,
             lobby_name: String::new(),
+            created: None,
             game_type: 0,
+            opponent: 0,
             selected: 0,
         }
     }
@@ -40,96 +66,144 @@ use std::collections::HashMap;
 
 #[async_trait]
 impl State for CreateGame {
-    async fn on_enter(&mut self) {}
+    async fn on_enter(&mut self, _events: EventSender) {}
 
     async fn on_update(&mut self) {}
 
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
-        terminal
-            .draw(|mut f| {
-                let chunks = Layout::default()
-                    .constraints(
-                        [
-                            Constraint::Length(1),
-                            Constraint::Length(3),
-                            Constraint::Length(1),
-                            Constraint::Length(1),
-                            Constraint::Length(3),
-                            Constraint::Length(1),
-                        ]
-                        .as_ref(),
-                    )
-                    .margin(1)
-                    .split(f.size());
-
-                Paragraph::new([Text::raw(String::from("Please enter a lobby name."))].iter())
-                    .style(Style::default().fg(Color::Blue))
-                    .render(&mut f, chunks[0]);
-
-                let selected_border_style = Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan));
-
-                let p1_border = if self.selected == 0 {
-                    selected_border_style
-                } else {
-                    Block::default().borders(Borders::ALL)
-                };
-
-                let p2_border = if self.selected == 1 {
-                    selected_border_style
-                } else {
-                    Block::default().borders(Borders::ALL)
-                };
-
-                Paragraph::new([Text::raw(self.lobby_name.clone())].iter())
-                    .block(p1_border)
-                    .render(&mut f, chunks[1]);
-
-                Paragraph::new([Text::raw(String::from("Please select a game type."))].iter())
-                    .style(Style::default().fg(Color::Blue))
-                    .render(&mut f, chunks[3]);
-
-                Tabs::default()
-                    .block(p2_border)
-                    .titles(&["Tic-Tac-Toe", "Rock Paper Scissors"])
-                    .style(Style::default().fg(Color::White))
-                    .select(self.game_type)
-                    .highlight_style(
-                        Style::default()
-                            .fg(Color::Green)
-                            .modifier(Modifier::UNDERLINED),
-                    )
-                    .render(&mut f, chunks[4])
-            })
-            .unwrap();
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
+        terminal.draw(|mut f| {
+            let chunks = Layout::default()
+                .constraints(
+                    [
+                        Constraint::Length(1),
+                        Constraint::Length(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                        Constraint::Length(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                        Constraint::Length(3),
+                        Constraint::Length(1),
+                        Constraint::Length(1),
+                    ]
+                    .as_ref(),
+                )
+                .margin(1)
+                .split(f.size());
+
+            Paragraph::new([Text::raw(String::from("Please enter a lobby name."))].iter())
+                .style(theme.status)
+                .render(&mut f, chunks[0]);
+
+            let selected_border_style = Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.focus);
+
+            let p1_border = if self.selected == 0 {
+                selected_border_style
+            } else {
+                Block::default().borders(Borders::ALL)
+            };
+
+            let p2_border = if self.selected == 1 {
+                selected_border_style
+            } else {
+                Block::default().borders(Borders::ALL)
+            };
+
+            let p3_border = if self.selected == 2 {
+                selected_border_style
+            } else {
+                Block::default().borders(Borders::ALL)
+            };
+
+            Paragraph::new([Text::raw(self.lobby_name.clone())].iter())
+                .style(theme.text)
+                .block(p1_border)
+                .render(&mut f, chunks[1]);
+
+            Paragraph::new([Text::raw(String::from("Please select a game type."))].iter())
+                .style(theme.status)
+                .render(&mut f, chunks[3]);
+
+            Tabs::default()
+                .block(p2_border)
+                .titles(&["Tic-Tac-Toe", "Rock Paper Scissors"])
+                .style(theme.text)
+                .select(self.game_type)
+                .highlight_style(theme.selection)
+                .render(&mut f, chunks[4]);
+
+            Paragraph::new([Text::raw(String::from("Please select an opponent."))].iter())
+                .style(theme.status)
+                .render(&mut f, chunks[6]);
+
+            Tabs::default()
+                .block(p3_border)
+                .titles(&OPPONENTS)
+                .style(theme.text)
+                .select(self.opponent)
+                .highlight_style(theme.selection)
+                .render(&mut f, chunks[7]);
+
+            let (status, style) = match &self.error {
+                _ if self.pending.is_busy() => (
+                    format!("Creating the game {}", network::spinner()),
+                    theme.busy,
+                ),
+                Some(error) => (error.clone(), theme.error),
+                None => (String::new(), theme.text),
+            };
+
+            Paragraph::new([Text::raw(status)].iter())
+                .style(style)
+                .render(&mut f, chunks[9]);
+        })
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(key) => match key {
-                Key::Down => {
+            Event::Input(Key::Char(c)) if !c.is_control() => {
+                if self.selected == 0 {
+                    self.lobby_name.push(c);
+                }
+            }
+            Event::Input(Key::Backspace) => {
+                if self.selected == 0 {
+                    self.lobby_name.pop();
+                }
+            }
+            Event::Command(command) => match command {
+                Command::Back => return Action::Pop,
+                Command::Home => return Action::PopToRoot,
+                Command::Down => {
                     self.selected += 1;
                     if self.selected > SELECTION_MAX - 1 {
                         self.selected = 0;
                     }
                 }
-                Key::Up => {
+                Command::Up => {
                     if self.selected > 0 {
                         self.selected -= 1;
                     } else {
                         self.selected = SELECTION_MAX - 1;
                     }
                 }
-                Key::Right => {
+                Command::Right => {
                     if self.selected == 1 {
                         self.game_type += 1;
                         if self.game_type > 2 - 1 {
                             self.game_type = 0;
                         }
                     }
+                    if self.selected == 2 {
+                        self.opponent += 1;
+                        if self.opponent > OPPONENTS.len() - 1 {
+                            self.opponent = 0;
+                        }
+                    }
                 }
-                Key::Left => {
+                Command::Left => {
                     if self.selected == 1 {
                         if self.game_type > 0 {
                             self.game_type -= 1;
@@ -137,67 +211,67 @@ impl State for CreateGame {
                             self.game_type = 2 - 1;
                         }
                     }
+                    if self.selected == 2 {
+                        if self.opponent > 0 {
+                            self.opponent -= 1;
+                        } else {
+                            self.opponent = OPPONENTS.len() - 1;
+                        }
+                    }
                 }
-                Key::Char('\n') => {
-                    if self.selected == 1 {
+                Command::Confirm => {
+                    if (self.selected == 1 || self.selected == 2) && !self.pending.is_busy() {
                         // try and create it.
-                        let url = format!("http://localhost:8000/lobbies");
-
                         let game_type = if self.game_type == 0 {
                             GameType::TicTacToe
                         } else {
                             GameType::RockPaperScissors
                         };
 
-                        let _res = surf::post(url)
-                            .body_json(&common::CreateLobbyRequest {
-                                name: self.lobby_name.clone(),
-                                game: game_type,
-                            })
-                            .unwrap()
-                            .await
-                            .unwrap();
-
-                        let url = format!("http://localhost:8000/lobbies/{}/join", self.lobby_name);
-
-                        let res: Result<common::JoinResponse, _> =
-                            surf::post(url).await.unwrap().body_json().await;
-
-                        if let Ok(res) = res {
-                            use crate::states::{RockPaperScissors, TicTacToe};
-
-                            match res.game_type {
-                                GameType::TicTacToe => {
-                                    return Action::PushState(Box::new(TicTacToe::new(
-                                        res.player,
-                                        &self.lobby_name,
-                                    )));
-                                }
-                                GameType::RockPaperScissors => {
-                                    return Action::PushState(Box::new(RockPaperScissors::new(
-                                        res.player,
-                                        &self.lobby_name,
-                                    )));
-                                }
-                            }
-                        }
+                        let bot = match self.opponent {
+                            1 => Some(BotDifficulty::Easy),
+                            2 => Some(BotDifficulty::Medium),
+                            3 => Some(BotDifficulty::Hard),
+                            _ => None,
+                        };
+
+                        let id = self.network.send(Request::CreateLobby(CreateLobbyRequest {
+                            name: self.lobby_name.clone(),
+                            game: game_type,
+                            bot,
+                        }));
+
+                        self.pending.add(id);
+                        self.error = None;
                     }
                 }
-                Key::Char(c) => {
-                    if self.selected == 0 {
-                        self.lobby_name.push(c);
-                    }
+                _ => {}
+            },
+            Event::Response(id, response) if self.pending.finish(id) => match response {
+                Response::Created(lobby) => {
+                    let id = self.network.send(Request::Join(lobby.clone()));
+                    self.pending.add(id);
+                    self.created = Some(lobby);
                 }
-                Key::Backspace => {
-                    if self.selected == 0 {
-                        self.lobby_name.pop();
+                Response::Joined(join) => {
+                    if let Some(lobby) = self.created.take() {
+                        let network = self.network.clone();
+                        return Action::Replace(states::game(network, join, &lobby, self.board));
                     }
                 }
                 _ => {}
             },
+            Event::Failure(id, error) if self.pending.finish(id) => {
+                self.error = Some(error.to_string());
+            }
             _ => {}
         }
 
         Action::None
     }
+
+    /// Only while the lobby name is selected.
+    fn takes_text(&self) -> bool {
+        self.selected == 0
+    }
 }
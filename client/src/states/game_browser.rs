@@ -4,172 +4,418 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
 use tui::backend::TermionBackend;
-use tui::layout::{Constraint, Layout};
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
-use tui::widgets::{Block, Borders, Row, Table, Widget};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, Text, Widget};
 
-use common::GameType;
-use common::Lobby;
+use std::cmp::Ordering;
+use std::io;
+use std::time::{Duration, Instant};
+
+use common::{GameType, Lobby, LobbyQuery, LobbyStatus};
+
+use crate::config::BoardStyle;
+use crate::keymap::Command;
+use crate::states;
+use crate::theme::Theme;
+use crate::util::mouse;
+use crate::util::network::{self, Network, Pending, Request, Response};
+
+/// How often the lobbies are fetched again while they're shown.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The most lobbies asked for at once, so a busy server doesn't send every one of them on every
+/// refresh.
+const MAX_LOBBIES: usize = 200;
+
+const COLUMNS: [&str; 4] = ["Lobby Name", "Game Type", "Status", "Players"];
+
+/// What the lobbies can be narrowed down to, in the order `Command::Filter` goes through them.
+const GAME_TYPES: [Option<GameType>; 3] = [
+    None,
+    Some(GameType::TicTacToe),
+    Some(GameType::RockPaperScissors),
+];
 
 pub struct GameBrowser {
+    /// The lobbies last fetched.
+    lobbies: Vec<Lobby>,
+    /// The ones shown, filtered and sorted.
     items: Vec<Lobby>,
     selected: usize,
-    server_address: String,
+    network: Network,
+    /// How the game screen draws the board.
+    board: BoardStyle,
+    pending: Pending,
+    /// The ID of the lobby being joined, while waiting on the server
+    joining: Option<String>,
+    /// Why the last request failed
+    error: Option<String>,
+    /// Where the table was last drawn, for finding the lobby clicked on
+    table_area: Rect,
+    /// When the lobbies were last asked for, `None` to ask again on the next update
+    fetched: Option<Instant>,
+    /// Index into `COLUMNS`
+    sort_by: usize,
+    descending: bool,
+    /// Index into `GAME_TYPES`
+    game_type: usize,
+    open_only: bool,
+    search: String,
+    /// Whether keys are going into the search box
+    searching: bool,
 }
 
 impl GameBrowser {
-    pub fn new(server_address: &str) -> Self {
+    pub fn new(network: Network, board: BoardStyle) -> Self {
         Self {
+            lobbies: vec![],
             items: vec![],
             selected: 0,
-            server_address: server_address.into(),
+            network,
+            board,
+            pending: Pending::default(),
+            joining: None,
+            error: None,
+            table_area: Rect::default(),
+            fetched: None,
+            sort_by: 0,
+            descending: false,
+            game_type: 0,
+            open_only: false,
+            search: String::new(),
+            searching: false,
         }
     }
-}
 
-use async_trait::async_trait;
-use std::collections::HashMap;
+    /// The filters as the server takes them. They're applied again when showing the lobbies, for
+    /// servers that don't support them.
+    fn query(&self) -> LobbyQuery {
+        LobbyQuery {
+            game_type: GAME_TYPES[self.game_type].clone(),
+            status: if self.open_only {
+                Some(LobbyStatus::Open)
+            } else {
+                None
+            },
+            name: if self.search.is_empty() {
+                None
+            } else {
+                Some(self.search.clone())
+            },
+            offset: None,
+            limit: Some(MAX_LOBBIES),
+        }
+    }
 
-#[async_trait]
-impl State for GameBrowser {
-    async fn on_update(&mut self) {}
+    fn fetch(&mut self) {
+        let id = self.network.send(Request::Lobbies(self.query()));
+        self.pending.add(id);
+        self.fetched = Some(Instant::now());
+    }
 
-    async fn on_enter(&mut self) {
+    /// Show the lobbies matching the new filters right away, and fetch them again in case the
+    /// server left any out under the old ones.
+    fn filters_changed(&mut self) {
+        self.show();
+        self.fetched = None;
+    }
+
+    fn join_selected(&mut self) {
+        if !self.items.is_empty() && self.joining.is_none() {
+            let lobby = self.items[self.selected].id.clone();
+
+            let id = self.network.send(Request::Join(lobby.clone()));
+            self.pending.add(id);
+            self.joining = Some(lobby);
+            self.error = None;
+        }
+    }
+
+    fn show_lobbies(&mut self, lobbies: Vec<Lobby>) {
+        self.lobbies = lobbies;
+        self.error = None;
+        self.show();
+    }
+
+    /// Filter and sort the lobbies into `items`, keeping the same lobby selected if it's still
+    /// there.
+    fn show(&mut self) {
+        let selected = self
+            .items
+            .get(self.selected)
+            .map(|lobby| lobby.id.clone());
+        let query = self.query();
+
+        self.items = self
+            .lobbies
+            .iter()
+            .filter(|lobby| query.matches(lobby))
+            .cloned()
+            .collect();
+
+        let sort_by = self.sort_by;
+        self.items
+            .sort_by(|a, b| compare(sort_by, a, b).then_with(|| a.by_name(b)));
+
+        if self.descending {
+            self.items.reverse();
+        }
+
+        // The list may have shrunk since the last visit.
+        self.selected = selected
+            .and_then(|id| self.items.iter().position(|lobby| lobby.id == id))
+            .unwrap_or(self.selected)
+            .min(self.items.len().saturating_sub(1));
+    }
+
+    fn title(&self) -> String {
+        let mut filters = vec![];
+
+        if let Some(game_type) = &GAME_TYPES[self.game_type] {
+            filters.push(game_name(game_type));
+        }
+
+        if self.open_only {
+            filters.push("open seats");
+        }
+
+        let mut title = String::from("Game List");
+
+        if !filters.is_empty() {
+            title = format!("{}, only {}", title, filters.join(", "));
+        }
+
+        match &self.error {
+            _ if self.pending.is_busy() => format!("{} {}", title, network::spinner()),
+            Some(error) => format!("{} ({})", title, error),
+            None => title,
+        }
+    }
+}
 
-        let url = format!("http://{}/lobbies", self.server_address);
+/// Orders lobbies by one of `COLUMNS`.
+fn compare(column: usize, a: &Lobby, b: &Lobby) -> Ordering {
+    match column {
+        0 => a.by_name(b),
+        1 => game_name(&a.game_type).cmp(game_name(&b.game_type)),
+        2 => a.status.cmp(&b.status),
+        _ => a.players.cmp(&b.players),
+    }
+}
 
-        let lobbies: HashMap<String, Lobby> =
-            surf::get(url)
-                .await
-                .unwrap()
-                .body_json()
-                .await
-                .unwrap();
+fn status_name(status: LobbyStatus) -> &'static str {
+    match status {
+        LobbyStatus::Open => "Open",
+        LobbyStatus::InProgress => "In progress",
+        LobbyStatus::Finished => "Finished",
+        LobbyStatus::Abandoned => "Abandoned",
+    }
+}
 
-        // fetch games
-        /*let lobbies: Result<HashMap<String, Lobby>, _> = surf::get("http://localhost:8000/lobbies")
-        .await
-        .unwrap()
-        .body_json().await;*/
+fn game_name(game_type: &GameType) -> &'static str {
+    match game_type {
+        GameType::TicTacToe => "Tic-Tac-Toe",
+        GameType::RockPaperScissors => "Rock Paper Scissors",
+    }
+}
 
-        //println!("{:?}", lobbies);
+use async_trait::async_trait;
 
-        self.items.clear();
+#[async_trait]
+impl State for GameBrowser {
+    async fn on_update(&mut self) {
+        let due = self
+            .fetched
+            .map_or(true, |fetched| fetched.elapsed() >= REFRESH_INTERVAL);
 
-        for (_, lobby) in lobbies {
-            self.items.push(lobby);
+        if due && !self.pending.is_busy() {
+            self.fetch();
         }
+    }
 
-        /*
-        vec![
-                format!("{}", lobby.name),
-                format!("{}", "127.0.0.1"),
-                format!("{}", "TicTacToe"), // #TODO!
-                format!("({}/{})", lobby.players, lobby.max_players),
-            ]
-            */
+    async fn on_enter(&mut self, _events: EventSender) {
+        self.fetch();
     }
 
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
+    /// Lobbies may have come and gone while we were in a game.
+    async fn on_resume(&mut self, events: EventSender) {
+        self.on_enter(events).await;
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
         terminal
             .draw(|mut f| {
-                let selected_style = Style::default()
-                    .fg(Color::Green)
-                    .modifier(Modifier::UNDERLINED);
-                let normal_style = Style::default().fg(Color::White);
-                let header = ["Lobby Name", "Server IP", "Game Type", "Players"];
+                let rects = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+                    .margin(1)
+                    .split(f.size());
+                self.table_area = rects[1];
+
+                let search_border = if self.searching {
+                    theme.focus
+                } else {
+                    theme.text
+                };
+
+                Paragraph::new([Text::raw(self.search.clone())].iter())
+                    .style(theme.text)
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(search_border)
+                            .title("Search by name"),
+                    )
+                    .render(&mut f, rects[0]);
+
+                // Mark the column the lobbies are sorted by with which way they're sorted.
+                let header: Vec<String> = COLUMNS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| match i {
+                        _ if i != self.sort_by => column.to_string(),
+                        _ if self.descending => format!("{} v", column),
+                        _ => format!("{} ^", column),
+                    })
+                    .collect();
+
+                let title = self.title();
+
                 let rows = self.items.iter().enumerate().map(|(i, lobby)| {
                     let data = vec![
                         format!("{}", lobby.name),
-                        format!("{}", "127.0.0.1"),
-                        format!("{:?}", lobby.game_type), // #TODO!
+                        format!("{}", game_name(&lobby.game_type)),
+                        format!("{}", status_name(lobby.status)),
                         format!("({}/{})", lobby.players, lobby.max_players),
                     ];
 
+                    // Dim the lobbies that can't be joined any more.
+                    let closed = match lobby.status {
+                        LobbyStatus::Finished | LobbyStatus::Abandoned => true,
+                        _ => false,
+                    };
+
                     if i == self.selected {
-                        Row::StyledData(data.into_iter(), selected_style)
+                        Row::StyledData(data.into_iter(), theme.selection)
+                    } else if closed {
+                        Row::StyledData(data.into_iter(), theme.invalid)
                     } else {
-                        Row::StyledData(data.into_iter(), normal_style)
+                        Row::StyledData(data.into_iter(), theme.text)
                     }
                 });
 
-                let rects = Layout::default()
-                    .constraints([Constraint::Percentage(100)].as_ref())
-                    .margin(1)
-                    .split(f.size());
                 Table::new(header.iter(), rows)
-                    .header_style(Style::default().fg(Color::Blue))
-                    .block(Block::default().borders(Borders::ALL).title("Game List"))
+                    .header_style(theme.status)
+                    .block(Block::default().borders(Borders::ALL).title(&title))
                     .widths(&[
-                        Constraint::Percentage(25),
-                        Constraint::Percentage(25),
-                        Constraint::Percentage(25),
-                        Constraint::Percentage(25),
+                        Constraint::Percentage(35),
+                        Constraint::Percentage(30),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(15),
                     ])
                     .column_spacing(1)
-                    .render(&mut f, rects[0]);
+                    .render(&mut f, rects[1]);
             })
-            .unwrap();
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(key) => match key {
-                Key::Down => {
+            Event::Input(Key::Char(c)) if self.searching && !c.is_control() => {
+                self.search.push(c);
+                self.filters_changed();
+            }
+            Event::Input(Key::Backspace) if self.searching => {
+                self.search.pop();
+                self.filters_changed();
+            }
+            Event::Command(Command::Confirm) if self.searching => self.searching = false,
+            Event::Command(Command::Back) if self.searching => {
+                self.searching = false;
+                self.search.clear();
+                self.filters_changed();
+            }
+            Event::Command(command) => match command {
+                Command::Back => return Action::Pop,
+                Command::Home => return Action::PopToRoot,
+                Command::Quit => return Action::Quit,
+                Command::Down if !self.items.is_empty() => {
                     self.selected += 1;
                     if self.selected > self.items.len() - 1 {
                         self.selected = 0;
                     }
                 }
-                Key::Up => {
+                Command::Up if !self.items.is_empty() => {
                     if self.selected > 0 {
                         self.selected -= 1;
                     } else {
                         self.selected = self.items.len() - 1;
                     }
                 }
-                Key::Char('\n') => {
-                    if !self.items.is_empty() {
-                        let lobby = &self.items[self.selected];
-
-                        let url =
-                            format!("http://{}/lobbies/{}/join", self.server_address, lobby.name);
-
-                        let res: Result<common::JoinResponse, _> =
-                            surf::post(url).await.unwrap().body_json().await;
-
-                        if let Ok(res) = res {
-                            use crate::states::{RockPaperScissors, TicTacToe};
-
-                            match res.game_type {
-                                GameType::TicTacToe => {
-                                    return Action::PushState(Box::new(TicTacToe::new(
-                                        res.player,
-                                        &lobby.name,
-                                    )));
-                                }
-                                GameType::RockPaperScissors => {
-                                    return Action::PushState(Box::new(RockPaperScissors::new(
-                                        res.player,
-                                        &lobby.name,
-                                    )));
-                                }
-                            }
-                        }
+                Command::Left => {
+                    self.sort_by = (self.sort_by + COLUMNS.len() - 1) % COLUMNS.len();
+                    self.show();
+                }
+                Command::Right => {
+                    self.sort_by = (self.sort_by + 1) % COLUMNS.len();
+                    self.show();
+                }
+                Command::Reverse => {
+                    self.descending = !self.descending;
+                    self.show();
+                }
+                Command::Filter => {
+                    self.game_type = (self.game_type + 1) % GAME_TYPES.len();
+                    self.filters_changed();
+                }
+                Command::OpenOnly => {
+                    self.open_only = !self.open_only;
+                    self.filters_changed();
+                }
+                Command::Search => self.searching = true,
+                Command::Retry if !self.pending.is_busy() => self.fetch(),
+                Command::Confirm => self.join_selected(),
+                _ => {}
+            },
+            Event::Mouse(event) => {
+                // The table's header and the gap under it come before the lobbies.
+                let row = mouse::left_click(&event)
+                    .and_then(|point| mouse::list_row(self.table_area, 2, self.items.len(), point));
+
+                match row {
+                    Some(row) if row == self.selected => self.join_selected(),
+                    Some(row) => self.selected = row,
+                    None => {}
+                }
+            }
+            Event::Response(id, response) if self.pending.finish(id) => match response {
+                Response::Lobbies(lobbies) => self.show_lobbies(lobbies),
+                Response::Joined(join) => {
+                    if let Some(lobby) = self.joining.take() {
+                        let network = self.network.clone();
+                        return Action::PushState(states::game(network, join, &lobby, self.board));
                     }
                 }
                 _ => {}
             },
+            Event::Failure(id, error) if self.pending.finish(id) => {
+                self.joining = None;
+                self.error = Some(error.to_string());
+            }
             _ => {}
         }
 
         Action::None
     }
+
+    fn takes_text(&self) -> bool {
+        self.searching
+    }
 }
@@ -4,14 +4,14 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
 use tui::backend::TermionBackend;
-use tui::layout::{Constraint, Direction, Layout};
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
 use tui::widgets::{Block, Borders, List, Paragraph, Row, SelectableList, Table, Text, Widget};
 use unicode_width::UnicodeWidthStr;
@@ -22,18 +22,47 @@ use termion::cursor::Goto;
 
 use crate::states::CreateGame;
 use crate::states::GameBrowser;
+
+use crate::config::BoardStyle;
+use crate::keymap::Command;
+use crate::theme::Theme;
+use crate::util::mouse;
+use crate::util::network::{self, Network, Request, RequestId};
+
 pub struct MainMenu {
     selected: usize,
     items: Vec<String>,
-    address: String,
+    network: Network,
+    /// How the game screens draw boards, from the config.
+    board: BoardStyle,
+
+    /// The handshake made on entering the menu, while it's in flight.
+    checking: Option<RequestId>,
+    /// Why the server can't be used, from the handshake.
+    server_error: Option<String>,
+
+    /// Where the menu was last drawn, for finding the item clicked on.
+    list_area: Rect,
 }
 
 impl MainMenu {
-    pub fn new(server_address: &str) -> Self {
+    pub fn new(network: Network, board: BoardStyle) -> Self {
         Self {
             selected: 0,
             items: vec![String::from("Create a game"), String::from("Join a game")],
-            address: server_address.to_owned(),
+            network,
+            board,
+            checking: None,
+            server_error: None,
+            list_area: Rect::default(),
+        }
+    }
+
+    fn open_selected(&self) -> Action {
+        match self.selected {
+            0 => Action::PushState(Box::new(CreateGame::new(self.network.clone(), self.board))),
+            1 => Action::PushState(Box::new(GameBrowser::new(self.network.clone(), self.board))),
+            _ => Action::None,
         }
     }
 }
@@ -44,40 +73,70 @@ use async_trait::async_trait;
 impl State for MainMenu {
     async fn on_update(&mut self) {}
 
-    async fn on_enter(&mut self) {}
-
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
-        terminal
-            .draw(|mut f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints([Constraint::Percentage(100)].as_ref())
-                    .margin(1)
-                    .split(f.size());
-
-                SelectableList::default()
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Select an Action"),
-                    )
-                    .items(&self.items)
-                    .select(Some(self.selected))
-                    .highlight_style(
-                        Style::default()
-                            .fg(Color::LightGreen)
-                            .modifier(Modifier::BOLD),
-                    )
-                    .highlight_symbol(">")
-                    .render(&mut f, chunks[0]);
-            })
-            .unwrap();
+    async fn on_enter(&mut self, _events: EventSender) {
+        self.checking = Some(self.network.send(Request::Version));
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
+        terminal.draw(|mut f| {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(100)].as_ref())
+                .margin(1)
+                .split(f.size());
+
+            if let Some(error) = &self.server_error {
+                Paragraph::new(
+                    [
+                        Text::raw(format!("{}\n\n", error)),
+                        Text::raw("Go back to pick another server, or quit."),
+                    ]
+                    .iter(),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Can't use this server"),
+                )
+                .style(theme.error)
+                .wrap(true)
+                .render(&mut f, chunks[0]);
+                return;
+            }
+
+            let title = match self.checking {
+                Some(_) => format!(
+                    "Select an Action (checking the server {})",
+                    network::spinner()
+                ),
+                None => String::from("Select an Action"),
+            };
+
+            self.list_area = chunks[0];
+
+            SelectableList::default()
+                .block(Block::default().borders(Borders::ALL).title(&title))
+                .items(&self.items)
+                .select(Some(self.selected))
+                .style(theme.text)
+                .highlight_style(theme.selection)
+                .highlight_symbol(">")
+                .render(&mut f, chunks[0]);
+        })
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(input) => match input {
-                Key::Down => {
+            Event::Command(Command::Back) => return Action::Pop,
+            Event::Command(Command::Quit) => return Action::Quit,
+            Event::Response(id, _) if self.checking == Some(id) => self.checking = None,
+            Event::Failure(id, error) if self.checking == Some(id) => {
+                self.checking = None;
+                self.server_error = Some(error.to_string());
+            }
+            _ if self.server_error.is_some() => return Action::None,
+            Event::Command(command) => match command {
+                Command::Down => {
                     self.selected = {
                         if self.selected >= self.items.len() - 1 {
                             0
@@ -86,7 +145,7 @@ impl State for MainMenu {
                         }
                     };
                 }
-                Key::Up => {
+                Command::Up => {
                     self.selected = {
                         if self.selected > 0 {
                             self.selected - 1
@@ -95,16 +154,19 @@ impl State for MainMenu {
                         }
                     };
                 }
-                Key::Char('\n') => {
-                    if self.selected == 0 {
-                        return Action::PushState(Box::new(CreateGame::new()));
-                    }
-                    if self.selected == 1 {
-                        return Action::PushState(Box::new(GameBrowser::new(&self.address)));
-                    }
-                }
+                Command::Confirm => return self.open_selected(),
                 _ => {}
             },
+            Event::Mouse(event) => {
+                let row = mouse::left_click(&event)
+                    .and_then(|point| mouse::list_row(self.list_area, 0, self.items.len(), point));
+
+                match row {
+                    Some(row) if row == self.selected => return self.open_selected(),
+                    Some(row) => self.selected = row,
+                    None => {}
+                }
+            }
             _ => {}
         }
 
@@ -11,3 +11,24 @@ pub use game_browser::GameBrowser;
 pub use mainmenu::MainMenu;
 pub use rockpaperscissors::RockPaperScissors;
 pub use tictactoe::TicTacToe;
+
+use common::{GameType, JoinResponse};
+
+use crate::config::BoardStyle;
+use crate::state::State;
+use crate::util::network::Network;
+
+/// The screen for playing the game just joined in `lobby`.
+pub fn game(
+    network: Network,
+    join: JoinResponse,
+    lobby: &str,
+    board: BoardStyle,
+) -> Box<dyn State> {
+    match join.game_type {
+        GameType::TicTacToe => Box::new(TicTacToe::new(network, join.player, lobby, board)),
+        GameType::RockPaperScissors => {
+            Box::new(RockPaperScissors::new(network, join.player, lobby))
+        }
+    }
+}
@@ -4,14 +4,14 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
 use termion::raw::IntoRawMode;
 use termion::screen::AlternateScreen;
 use tui::backend::TermionBackend;
-use tui::layout::{Constraint, Direction, Layout};
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
 use tui::widgets::{Block, Borders, List, Paragraph, Row, Table, Tabs, Text, Widget};
 use unicode_width::UnicodeWidthStr;
@@ -21,10 +21,32 @@ use uuid::Uuid;
 
 use termion::cursor::Goto;
 
+use client_sdk::Watch;
 use common::rockpaperscissors::*;
+use common::rockpaperscissors_strategy;
 use common::Game;
+use common::ServerMessage;
+
+use crate::keymap::Command;
+use crate::theme::Theme;
+use crate::util::mouse;
+use crate::util::network::{self, Network, Pending, Request, Response};
+
+/// The moves to pick from, in the order of `move_selection`.
+const MOVES: [&str; 3] = ["Rock", "Paper", "Scissors"];
+
+/// Where `player_move` is in `MOVES`.
+fn move_index(player_move: Move) -> usize {
+    match player_move {
+        Move::Rock => 0,
+        Move::Paper => 1,
+        Move::Scissors => 2,
+    }
+}
 
 pub struct RockPaperScissors {
+    network: Network,
+    pending: Pending,
     address: String,
     /// Current value of the input box
     input: String,
@@ -39,11 +61,20 @@ pub struct RockPaperScissors {
     round: usize,
     move_selection: usize,
     history: Vec<HistoryEntry>,
+    suggestion: Option<Move>,
+
+    /// Pushes updates to the game as they happen.
+    connection: Option<Watch>,
+
+    /// Where the moves were last drawn, for finding the one clicked on.
+    tabs_area: Rect,
 }
 
 impl RockPaperScissors {
-    pub fn new(player: Uuid, lobby: &str) -> Self {
+    pub fn new(network: Network, player: Uuid, lobby: &str) -> Self {
         Self {
+            network,
+            pending: Pending::default(),
             address: String::new(),
             input: String::new(),
             messages: Vec::new(),
@@ -58,29 +89,50 @@ impl RockPaperScissors {
             move_selection: 0,
 
             history: vec![],
+            suggestion: None,
+            connection: None,
+            tabs_area: Rect::default(),
         }
     }
 
-    pub async fn fetch_state(&mut self) {
-        // fetch the game state
-        let url = format!("http://localhost:8000/lobbies/{}/state", self.lobby);
+    fn play_selected(&mut self) {
+        let player_move = if self.move_selection == 0 {
+            Move::Rock
+        } else if self.move_selection == 1 {
+            Move::Paper
+        } else {
+            Move::Scissors
+        };
+
+        let action = common::Action::RockPaperScissors(PlayerAction::Move {
+            player: self.player,
+            action: player_move,
+        });
+
+        let id = self.network.send(Request::Act(self.lobby.clone(), action));
+        self.pending.add(id);
+    }
 
-        let game: Game = surf::get(url).await.unwrap().body_json().await.unwrap();
+    pub fn fetch_state(&mut self) {
+        let id = self.network.send(Request::State(self.lobby.clone()));
+        self.pending.add(id);
+    }
 
+    fn show_state(&mut self, game: Game) {
         match game {
-            Game::RockPaperScissors(s) => {
-                self.state = s;
+            Game::RockPaperScissors(state) => {
+                self.state = state;
+                self.update();
             }
-            _ => panic!("wrong game type"),
+            _ => self.status = String::from("The server sent a different game than this lobby's."),
         }
-
-        self.update()
     }
 
     pub fn update(&mut self) {
         match &self.state {
             GameState::WaitingForPlayers { .. } => {
-                self.status = format!("Waiting for another player before the game will begin.");
+                self.status =
+                    String::from("Waiting for another player before the game will begin.");
             }
             GameState::WaitingForInput {
                 players,
@@ -88,18 +140,30 @@ impl RockPaperScissors {
                 history,
                 ..
             } => {
+                if self.round != *round {
+                    self.suggestion = None;
+                }
+
                 self.round = *round;
                 self.history = history.clone();
 
-                self.status = format!("Waiting for input");
+                self.status = match self.suggestion {
+                    Some(suggestion) => format!(
+                        "Waiting for input, suggested move: {}",
+                        MOVES[move_index(suggestion)]
+                    ),
+                    None => String::from(
+                        "Waiting for input, see the help for getting a suggested move",
+                    ),
+                };
             }
             GameState::GameOver { winner, history } => {
                 self.history = vec![];
 
                 if self.player == *winner {
-                    self.status = format!("The game is over, you've won!");
+                    self.status = String::from("The game is over, you've won!");
                 } else {
-                    self.status = format!("The game is over, you've lost.");
+                    self.status = String::from("The game is over, you've lost.");
                 }
             }
         }
@@ -110,13 +174,18 @@ use async_trait::async_trait;
 
 #[async_trait]
 impl State for RockPaperScissors {
-    async fn on_enter(&mut self) {}
+    async fn on_enter(&mut self, _events: EventSender) {
+        self.connection = Some(self.network.watch(&self.lobby));
+        self.fetch_state();
+    }
+
+    async fn on_update(&mut self) {}
 
-    async fn on_update(&mut self) {
-        self.fetch_state().await;
+    async fn on_exit(&mut self) {
+        self.connection = None;
     }
 
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
         terminal
             .draw(|mut f| {
                 let chunks = Layout::default()
@@ -125,13 +194,19 @@ impl State for RockPaperScissors {
                     .constraints([Constraint::Percentage(10), Constraint::Percentage(80), Constraint::Percentage(10)].as_ref())
                     .split(f.size());
 
+                let status = if self.pending.is_busy() {
+                    format!("{} {}", self.status, network::spinner())
+                } else {
+                    self.status.clone()
+                };
+
                 Paragraph::new(
                     [Text::raw(
-                        self.status.clone(),
+                        status,
                     )]
                     .iter(),
                 )
-                .style(Style::default().fg(Color::Blue))
+                .style(theme.status)
                 .render(&mut f, chunks[0]);
 
                 let mut rows = vec![];
@@ -145,13 +220,13 @@ impl State for RockPaperScissors {
                     match entry.winner {
                         Some(p) => {
                             if p == self.player {
-                                rows.push(format!("You won that round!"));
+                                rows.push(String::from("You won that round!"));
                             } else {
-                                rows.push(format!("You lost that round."));
+                                rows.push(String::from("You lost that round."));
                             }
                         }
                         None => {
-                            rows.push(format!("It was a tie!"));
+                            rows.push(String::from("It was a tie!"));
                         }
                     }
                     
@@ -162,84 +237,81 @@ impl State for RockPaperScissors {
                 Paragraph::new(
                     rows.iter(),
                 )
-                .style(Style::default().fg(Color::Blue))
+                .style(theme.status)
                 .render(&mut f, chunks[1]);
 
+                self.tabs_area = chunks[2];
+
                 Tabs::default()
-                    .titles(&["Rock", "Paper", "Scissors"])
-                    .style(Style::default().fg(Color::White))
+                    .titles(&MOVES)
+                    .style(theme.text)
                     .select(self.move_selection)
-                    .highlight_style(
-                        Style::default()
-                            .fg(Color::Green)
-                            .modifier(Modifier::UNDERLINED),
-                    )
+                    .highlight_style(theme.selection)
                     .render(&mut f, chunks[2])
-            })
-            .unwrap();
+            })?;
 
         // Put the cursor back inside the input box
         write!(
             terminal.backend_mut(),
             "{}",
             Goto(4 + self.input.width() as u16, 4)
-        )
-        .unwrap();
+        )?;
         // stdout is buffered, flush it to see the effect immediately when hitting backspace
-        io::stdout().flush().ok();
+        io::stdout().flush()
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(input) => match input {
-                Key::Right => {
+            Event::Command(command) => match command {
+                Command::Back => return Action::Pop,
+                Command::Home => return Action::PopToRoot,
+                Command::Quit => return Action::Quit,
+                Command::Right => {
                     self.move_selection += 1;
                     if self.move_selection > 3 - 1 {
                         self.move_selection = 0;
                     }
                 }
-                Key::Left => {
+                Command::Left => {
                     if self.move_selection > 0 {
                         self.move_selection -= 1;
                     } else {
                         self.move_selection = 3 - 1;
                     }
                 },
-                Key::Char('\n') => {
-                    let player_move = if self.move_selection == 0 {
-                        Move::Rock
-                    } else if self.move_selection == 1 {
-                        Move::Paper
-                    } else {
-                        Move::Scissors
-                    };
-
-                    let url = format!("http://localhost:8000/lobbies/{}/action", self.lobby);
-
-                    let res: serde_json::Value = surf::post(url)
-                        .body_json(&common::Action::RockPaperScissors(PlayerAction::Move {
-                            player: self.player,
-                            action: player_move,
-                        }))
-                        .unwrap()
-                        .await
-                        .unwrap()
-                        .body_json()
-                        .await
-                        .unwrap();
-
-                    if let Ok(new_state) = serde_json::from_value::<Game>(res) {
-                        match new_state {
-                            Game::RockPaperScissors(new_state) => {
-                                self.state = new_state;
-                                self.update();
-                            }
-                            _ => panic!("wrong game type"),
-                        }
-                    }
-                },
+                Command::Suggest => {
+                    let suggestion = rockpaperscissors_strategy::suggest(&self.history, self.player);
+
+                    self.move_selection = move_index(suggestion);
+                    self.suggestion = Some(suggestion);
+                    self.update();
+                }
+                Command::Confirm => self.play_selected(),
                 _ => {}
             },
+            Event::Mouse(event) => {
+                let clicked = mouse::left_click(&event)
+                    .and_then(|point| mouse::tab(self.tabs_area, &MOVES, point));
+
+                match clicked {
+                    Some(selection) if selection == self.move_selection => self.play_selected(),
+                    Some(selection) => self.move_selection = selection,
+                    None => {}
+                }
+            }
+            Event::Server(ServerMessage::State(game @ Game::RockPaperScissors(_))) => {
+                self.show_state(game);
+            }
+            Event::Response(id, response) if self.pending.finish(id) => match response {
+                Response::State(game) | Response::Acted(game) => self.show_state(game),
+                _ => {}
+            },
+            Event::Failure(id, error) if self.pending.finish(id) => {
+                self.status = error.to_string();
+            }
+            Event::Server(ServerMessage::Error(error)) => {
+                self.status = error;
+            }
             _ => {}
         }
 
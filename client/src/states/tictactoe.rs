@@ -4,7 +4,7 @@ use crate::state::{Action, State};
 use tui::terminal::Terminal;
 
 use crate::state::StateManager;
-use crate::util::event::{Event, Events};
+use crate::util::event::{Event, EventSender, Events};
 
 use termion::event::Key;
 use termion::input::MouseTerminal;
@@ -22,60 +22,257 @@ use tui::widgets::{canvas::Canvas, Block, Borders, List, Paragraph, Row, Table,
 
 use unicode_width::UnicodeWidthStr;
 
+use std::collections::HashMap;
 use std::io::{self, Write};
 
 use termion::cursor::Goto;
 
 use uuid::Uuid;
 
+use client_sdk::Watch;
 use common::tictactoe::Board;
 use common::tictactoe::BoardCell;
 use common::tictactoe::GameState;
+use common::tictactoe::PlayerAction;
 use common::Game;
+use common::ServerMessage;
+
+use crate::config::BoardStyle;
+use crate::keymap::Command;
+use crate::theme::Theme;
+use crate::util::mouse;
+use crate::util::network::{self, Network, Pending, Request, Response};
+
+/// The canvas' extent on both axes: three 25-wide cells, with a line either side.
+const BOARD_BOUNDS: [f64; 2] = [0.0, 77.0];
+
+/// The smallest area the canvas is drawn in with `BoardStyle::Auto`, below which its lines run
+/// together and the text board is drawn instead.
+const CANVAS_MIN_SIZE: (u16, u16) = (26, 14);
+
+const COLUMNS: [char; 3] = ['a', 'b', 'c'];
 
 pub struct TicTacToe {
+    network: Network,
+    pending: Pending,
     board: Board,
+    /// Whether `board` is up to date with the server yet, rather than the empty one it starts as.
+    seen_board: bool,
     player_token: BoardCell,
+    /// The players' tokens, kept for once the game is over and they're no longer sent.
+    tokens: HashMap<Uuid, BoardCell>,
     selection: (i16, i16),
     player: Uuid,
     lobby: String,
     state: GameState,
     status: String,
+    legal_moves: Vec<(usize, usize)>,
+
+    /// Pushes updates to the game as they happen.
+    connection: Option<Watch>,
+
+    /// How to draw the board, from the config.
+    board_style: BoardStyle,
+    /// Where the board was last drawn, for finding the cell clicked on.
+    board_area: Rect,
+    /// Whether it was drawn as text rather than on a canvas.
+    text_board: bool,
 }
 
 impl TicTacToe {
-    pub fn new(player: Uuid, lobby: &str) -> Self {
+    pub fn new(network: Network, player: Uuid, lobby: &str, board_style: BoardStyle) -> Self {
         Self {
+            network,
+            pending: Pending::default(),
             board: [[None, None, None], [None, None, None], [None, None, None]],
+            seen_board: false,
             player_token: BoardCell::X,
+            tokens: HashMap::new(),
             selection: (0, 0),
             player,
             lobby: lobby.to_owned(),
             state: GameState::default(),
             status: String::from("waiting"),
+            legal_moves: vec![],
+            connection: None,
+            board_style,
+            board_area: Rect::default(),
+            text_board: false,
         }
     }
 
-    pub async fn fetch_state(&mut self) {
-        // fetch the game state
-        let url = format!("http://localhost:8000/lobbies/{}/state", self.lobby);
+    pub fn fetch_state(&mut self) {
+        let id = self.network.send(Request::State(self.lobby.clone()));
+        self.pending.add(id);
+    }
 
-        let game: Game = surf::get(url).await.unwrap().body_json().await.unwrap();
+    pub fn fetch_legal_moves(&mut self) {
+        let id = self
+            .network
+            .send(Request::LegalActions(self.lobby.clone(), self.player));
+        self.pending.add(id);
+    }
 
+    fn show_state(&mut self, game: Game) {
         match game {
-            Game::TicTacToe(s) => {
-                self.state = s;
+            Game::TicTacToe(state) => {
+                self.state = state;
+                self.fetch_legal_moves();
+                self.update();
             }
-            _ => panic!("wrong game type"),
+            _ => self.status = String::from("The server sent a different game than this lobby's."),
         }
+    }
 
-        self.update()
+    fn show_legal_moves(&mut self, actions: Vec<common::Action>) {
+        self.legal_moves = actions
+            .into_iter()
+            .filter_map(|action| match action {
+                common::Action::TicTacToe(PlayerAction::PlaceToken { position, .. }) => {
+                    Some(position)
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    fn place_token(&mut self) {
+        let (x, y) = self.selection;
+        let x = x as usize;
+        let y = y as usize;
+
+        if !self.legal_moves.contains(&(x, y)) {
+            self.status = String::from("You can't place a token there.");
+            return;
+        }
+
+        let action = common::Action::TicTacToe(PlayerAction::PlaceToken {
+            player: self.player,
+            position: (x, y),
+        });
+
+        let id = self.network.send(Request::Act(self.lobby.clone(), action));
+        self.pending.add(id);
+    }
+
+    /// Select `cell`, or place a token there if it's already selected.
+    fn pick_cell(&mut self, cell: (i16, i16)) {
+        if cell == self.selection {
+            self.place_token();
+        } else {
+            self.selection = cell;
+        }
+    }
+
+    /// The cell at a point on the screen, if it's on the board.
+    fn cell_at(&self, point: (u16, u16)) -> Option<(i16, i16)> {
+        if self.text_board {
+            text_cell(self.board_area, point)
+        } else {
+            canvas_cell(self.board_area, point)
+        }
+    }
+
+    /// The board as a grid of letters, with the columns lettered and the rows numbered from the
+    /// bottom like a chess board:
+    ///
+    /// ```text
+    ///    a   b   c
+    /// 3  X | . | O
+    ///   ---+---+---
+    /// 2  . |[X]| .
+    ///   ---+---+---
+    /// 1  O | . | .
+    /// ```
+    fn board_text(&self, theme: &Theme) -> Vec<Text<'static>> {
+        let mut text = vec![Text::styled("   a   b   c\n", theme.text)];
+
+        for y in (0..3).rev() {
+            text.push(Text::styled(format!("{} ", y + 1), theme.text));
+
+            for x in 0..3 {
+                let cell = self.board[x][y];
+                let name = cell.map_or(".", token_name);
+
+                let (content, style) = if (x as i16, y as i16) == self.selection {
+                    (format!("[{}]", name), theme.selection)
+                } else if cell.is_none() {
+                    let style = if self.legal_moves.contains(&(x, y)) {
+                        theme.valid
+                    } else {
+                        theme.invalid
+                    };
+                    (format!(" {} ", name), style)
+                } else if cell == Some(self.player_token) {
+                    (format!(" {} ", name), theme.own_token)
+                } else {
+                    (format!(" {} ", name), theme.opponent_token)
+                };
+
+                text.push(Text::styled(content, style));
+                text.push(Text::styled(if x < 2 { "|" } else { "\n" }, theme.text));
+            }
+
+            if y > 0 {
+                text.push(Text::styled("  ---+---+---\n", theme.text));
+            }
+        }
+
+        text
+    }
+
+    /// Whether the player has a token, rather than watching.
+    fn is_playing(&self) -> bool {
+        self.tokens.contains_key(&self.player)
+    }
+
+    /// The moves made since `self.board`, e.g. "Your opponent played O on b2.", so the status
+    /// says what happened rather than only showing it. Nothing for the first board seen, so
+    /// joining a game part way through doesn't list every move made before.
+    fn describe_moves(&self, board: &Board) -> Option<String> {
+        if !self.seen_board {
+            return None;
+        }
+
+        let mut moves = vec![];
+
+        for y in 0..3 {
+            for x in 0..3 {
+                let token = match (self.board[x][y], board[x][y]) {
+                    (None, Some(token)) => token,
+                    _ => continue,
+                };
+
+                let by = if !self.is_playing() {
+                    String::from(token_name(token))
+                } else if token == self.player_token {
+                    String::from("You")
+                } else {
+                    String::from("Your opponent")
+                };
+
+                moves.push(format!(
+                    "{} played {} on {}.",
+                    by,
+                    token_name(token),
+                    cell_name((x as i16, y as i16))
+                ));
+            }
+        }
+
+        if moves.is_empty() {
+            None
+        } else {
+            Some(moves.join(" "))
+        }
     }
 
     pub fn update(&mut self) {
         match self.state {
             GameState::WaitingForPlayers { .. } => {
-                self.status = format!("Waiting for another player");
+                // Nothing's been played yet, so the empty board is up to date.
+                self.seen_board = true;
+                self.status = String::from("Waiting for another player");
             }
             GameState::WaitingForInput {
                 active_player,
@@ -83,31 +280,60 @@ impl TicTacToe {
                 ref tokens,
                 ..
             } => {
-                self.board = board;
+                self.tokens = tokens
+                    .iter()
+                    .map(|(player, token)| (*player, *token))
+                    .collect();
+
+                // Spectators have no token, so keep the default colours for them.
+                if let Some(token) = self.tokens.get(&self.player) {
+                    self.player_token = *token;
+                }
 
-                self.player_token = *tokens.clone().get_by_left(&self.player).unwrap();
+                let moves = self.describe_moves(&board);
+                self.board = board;
+                self.seen_board = true;
 
-                if self.player == active_player {
-                    self.status = format!("It's your turn");
+                let turn = if self.player == active_player {
+                    format!("It's your turn, you're {}.", token_name(self.player_token))
+                } else if self.is_playing() {
+                    String::from("Waiting for the other player to make their move.")
                 } else {
-                    self.status = format!("Waiting for the other play to make their move.")
-                }
+                    String::from("Waiting for the next move.")
+                };
+
+                self.status = match moves {
+                    Some(moves) => format!("{} {}", moves, turn),
+                    None => turn,
+                };
             }
             GameState::GameOver { winner, board } => {
+                let moves = self.describe_moves(&board);
                 self.board = board;
+                self.seen_board = true;
 
-                match winner {
+                let result = match winner {
                     Some(winner) => {
                         if self.player == winner {
-                            self.status = format!("The game is over, you've won!");
+                            String::from("The game is over, you've won!")
+                        } else if self.is_playing() {
+                            String::from("The game is over, you've lost.")
                         } else {
-                            self.status = format!("The game is over, you've lost.");
+                            match self.tokens.get(&winner) {
+                                Some(token) => {
+                                    format!("The game is over, {} won.", token_name(*token))
+                                }
+                                None => String::from("The game is over."),
+                            }
                         }
                     }
-                    None => {
-                        self.status = format!("The game is over, it was a tie.");
-                    }
-                }
+                    None => String::from("The game is over, it was a tie."),
+                };
+
+                self.status = match moves {
+                    Some(moves) => format!("{} {}", moves, result),
+                    None => result,
+                };
             }
         }
     }
@@ -117,145 +343,202 @@ use async_trait::async_trait;
 
 #[async_trait]
 impl State for TicTacToe {
-    async fn on_enter(&mut self) {
-        self.fetch_state().await;
+    async fn on_enter(&mut self, _events: EventSender) {
+        self.connection = Some(self.network.watch(&self.lobby));
+        self.fetch_state();
     }
 
-    async fn on_update(&mut self) {
-        self.fetch_state().await;
+    async fn on_update(&mut self) {}
+
+    async fn on_exit(&mut self) {
+        self.connection = None;
     }
 
-    fn render(&mut self, terminal: &mut Terminal<Backend>) {
-        terminal
-            .draw(|mut f| {
-                let chunks = Layout::default()
-                    .direction(Direction::Vertical)
-                    .constraints([Constraint::Length(3), Constraint::Percentage(100)].as_ref())
-                    .margin(1)
-                    .split(f.size());
-
-                Paragraph::new([Text::raw(self.status.clone())].iter()).render(&mut f, chunks[0]);
-
-                Canvas::default()
-                    .block(Block::default().title("Tic-Tac-Toe").borders(Borders::ALL))
-                    .x_bounds([0.0, 77.0])
-                    .y_bounds([0.0, 77.0])
-                    .paint(|ctx| {
-                        // draw board
-                        for x in 0..3 {
-                            for y in 0..3 {
-                                ctx.draw(&Rectangle {
-                                    rect: Rect {
+    fn render(&mut self, terminal: &mut Terminal<Backend>, theme: &Theme) -> io::Result<()> {
+        terminal.draw(|mut f| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Percentage(100)].as_ref())
+                .margin(1)
+                .split(f.size());
+
+            let status = if self.pending.is_busy() {
+                format!("{} {}", self.status, network::spinner())
+            } else {
+                self.status.clone()
+            };
+
+            Paragraph::new([Text::raw(status)].iter())
+                .style(theme.status)
+                .wrap(true)
+                .render(&mut f, chunks[0]);
+
+            self.board_area = chunks[1];
+            self.text_board = match self.board_style {
+                BoardStyle::Auto => {
+                    chunks[1].width < CANVAS_MIN_SIZE.0 || chunks[1].height < CANVAS_MIN_SIZE.1
+                }
+                BoardStyle::Canvas => false,
+                BoardStyle::Text => true,
+            };
+
+            let title = format!("Tic-Tac-Toe, {} selected", cell_name(self.selection));
+
+            if self.text_board {
+                Paragraph::new(self.board_text(theme).iter())
+                    .block(Block::default().title(&title).borders(Borders::ALL))
+                    .render(&mut f, chunks[1]);
+                return;
+            }
+
+            Canvas::default()
+                .block(Block::default().title(&title).borders(Borders::ALL))
+                .x_bounds(BOARD_BOUNDS)
+                .y_bounds(BOARD_BOUNDS)
+                .paint(|ctx| {
+                    // draw board, dimming the cells we can't place a token in
+                    for x in 0..3 {
+                        for y in 0..3 {
+                            let color = if self.legal_moves.contains(&(x as usize, y as usize)) {
+                                theme.valid.fg
+                            } else {
+                                theme.invalid.fg
+                            };
+
+                            ctx.draw(&Rectangle {
+                                rect: Rect {
+                                    x: (x * 25) + 1,
+                                    y: (y * 25) + 1,
+                                    width: 25,
+                                    height: 25,
+                                },
+                                color,
+                            });
+                        }
+                    }
+
+                    ctx.layer();
+
+                    // draw board tokens
+                    let margin = 8;
+                    let half_margin = margin / 2;
+
+                    for x in 0..3u16 {
+                        for y in 0..3u16 {
+                            let cell = self.board[x as usize][y as usize];
+                            let color = if cell == Some(self.player_token) {
+                                theme.own_token.fg
+                            } else {
+                                theme.opponent_token.fg
+                            };
+
+                            match cell {
+                                Some(BoardCell::Circle) => {
+                                    let rect = Rect {
                                         x: (x * 25) + 1,
                                         y: (y * 25) + 1,
                                         width: 25,
                                         height: 25,
-                                    },
-                                    color: Color::White,
-                                });
-                            }
-                        }
+                                    };
 
-                        ctx.layer();
-
-                        // draw board tokens
-                        let margin = 8;
-                        let half_margin = margin / 2;
-
-                        for x in 0..3u16 {
-                            for y in 0..3u16 {
-                                match self.board[x as usize][y as usize] {
-                                    Some(BoardCell::Circle) => {
-                                        let rect = Rect {
-                                            x: (x * 25) + 1,
-                                            y: (y * 25) + 1,
-                                            width: 25,
-                                            height: 25,
-                                        };
-
-                                        ctx.draw(&Rectangle {
-                                            rect: Rect {
-                                                x: rect.x + half_margin,
-                                                y: rect.y + half_margin,
-                                                width: 25 - margin,
-                                                height: 25 - margin,
-                                            },
-                                            color: Color::White,
-                                        });
-                                    }
-                                    Some(BoardCell::X) => {
-                                        let rect = Rect {
-                                            x: (x * 25) + 1,
-                                            y: (y * 25) + 1,
-                                            width: 25,
-                                            height: 25,
-                                        };
-
-                                        let rect = Rect {
+                                    ctx.draw(&Rectangle {
+                                        rect: Rect {
                                             x: rect.x + half_margin,
                                             y: rect.y + half_margin,
                                             width: 25 - margin,
                                             height: 25 - margin,
-                                        };
-
-                                        ctx.draw(&Line {
-                                            x1: rect.x as f64,
-                                            y1: rect.y as f64,
-                                            x2: (rect.x + rect.width) as f64,
-                                            y2: (rect.y + rect.height) as f64,
-                                            color: Color::White,
-                                        });
-
-                                        ctx.draw(&Line {
-                                            x1: rect.x as f64,
-                                            y1: (rect.y + rect.height) as f64,
-                                            x2: (rect.x + rect.width) as f64,
-                                            y2: rect.y as f64,
-                                            color: Color::White,
-                                        });
-                                    }
-                                    _ => {}
+                                        },
+                                        color,
+                                    });
                                 }
+                                Some(BoardCell::X) => {
+                                    let rect = Rect {
+                                        x: (x * 25) + 1,
+                                        y: (y * 25) + 1,
+                                        width: 25,
+                                        height: 25,
+                                    };
+
+                                    let rect = Rect {
+                                        x: rect.x + half_margin,
+                                        y: rect.y + half_margin,
+                                        width: 25 - margin,
+                                        height: 25 - margin,
+                                    };
+
+                                    ctx.draw(&Line {
+                                        x1: rect.x as f64,
+                                        y1: rect.y as f64,
+                                        x2: (rect.x + rect.width) as f64,
+                                        y2: (rect.y + rect.height) as f64,
+                                        color,
+                                    });
+
+                                    ctx.draw(&Line {
+                                        x1: rect.x as f64,
+                                        y1: (rect.y + rect.height) as f64,
+                                        x2: (rect.x + rect.width) as f64,
+                                        y2: rect.y as f64,
+                                        color,
+                                    });
+                                }
+                                _ => {}
                             }
                         }
+                    }
 
-                        // draw selection
-
-                        let (x, y) = self.selection;
-                        let x = x as u16;
-                        let y = y as u16;
-
-                        let cell = self.board[x as usize][y as usize];
+                    // draw selection
 
-                        let color = {
-                            if cell == None {
-                                Color::Green
-                            } else if cell.is_some() && cell.unwrap() == self.player_token {
-                                Color::Blue
-                            } else {
-                                Color::Red
-                            }
-                        };
-
-                        ctx.draw(&Rectangle {
-                            rect: Rect {
-                                x: (x * 25) + 1,
-                                y: (y * 25) + 1,
-                                width: 25,
-                                height: 25,
-                            },
-                            color: color,
-                        });
-                    })
-                    .render(&mut f, chunks[1]);
-            })
-            .unwrap();
+                    let (x, y) = self.selection;
+                    let x = x as u16;
+                    let y = y as u16;
+
+                    let cell = self.board[x as usize][y as usize];
+
+                    let color = {
+                        if self.legal_moves.contains(&(x as usize, y as usize)) {
+                            theme.selection.fg
+                        } else if cell == None {
+                            theme.invalid.fg
+                        } else if cell == Some(self.player_token) {
+                            theme.own_token.fg
+                        } else {
+                            theme.opponent_token.fg
+                        }
+                    };
+
+                    ctx.draw(&Rectangle {
+                        rect: Rect {
+                            x: (x * 25) + 1,
+                            y: (y * 25) + 1,
+                            width: 25,
+                            height: 25,
+                        },
+                        color: color,
+                    });
+
+                    // and again just inside, so the selection shows without colours too
+                    ctx.draw(&Rectangle {
+                        rect: Rect {
+                            x: (x * 25) + 2,
+                            y: (y * 25) + 2,
+                            width: 23,
+                            height: 23,
+                        },
+                        color: color,
+                    });
+                })
+                .render(&mut f, chunks[1]);
+        })
     }
 
     async fn on_event(&mut self, event: Event) -> Action {
         match event {
-            Event::Input(input) => match input {
-                Key::Up => {
+            Event::Command(command) => match command {
+                Command::Back => return Action::Pop,
+                Command::Home => return Action::PopToRoot,
+                Command::Quit => return Action::Quit,
+                Command::Up => {
                     self.selection.1 = {
                         if self.selection.1 + 1 >= 3 {
                             0
@@ -264,7 +547,7 @@ impl State for TicTacToe {
                         }
                     }
                 }
-                Key::Down => {
+                Command::Down => {
                     self.selection.1 = {
                         if self.selection.1 - 1 < 0 {
                             2
@@ -273,7 +556,7 @@ impl State for TicTacToe {
                         }
                     }
                 }
-                Key::Right => {
+                Command::Right => {
                     self.selection.0 = {
                         if self.selection.0 + 1 >= 3 {
                             0
@@ -282,7 +565,7 @@ impl State for TicTacToe {
                         }
                     }
                 }
-                Key::Left => {
+                Command::Left => {
                     self.selection.0 = {
                         if self.selection.0 - 1 < 0 {
                             2
@@ -291,43 +574,103 @@ impl State for TicTacToe {
                         }
                     }
                 }
-                Key::Char('\n') => {
-                    let (x, y) = self.selection;
-                    let x = x as usize;
-                    let y = y as usize;
-
-                    use common::tictactoe::GameState;
-
-                    //self.board[x][y] = Some(self.player_token);
-                    let url = format!("http://localhost:8000/lobbies/{}/action", self.lobby);
-
-                    let res: serde_json::Value = surf::post(url)
-                        .body_json(&common::tictactoe::PlayerAction::PlaceToken {
-                            player: self.player,
-                            position: (x, y),
-                        })
-                        .unwrap()
-                        .await
-                        .unwrap()
-                        .body_json()
-                        .await
-                        .unwrap();
-
-                    if let Ok(new_state) = serde_json::from_value::<Game>(res) {
-                        match new_state {
-                            Game::TicTacToe(new_state) => {
-                                self.state = new_state;
-                                self.update();
-                            }
-                            _ => panic!("wrong game type"),
-                        }
+                Command::Confirm => self.place_token(),
+                _ => {
+                    if let Some(n) = command.cell() {
+                        self.pick_cell((((n - 1) % 3) as i16, ((n - 1) / 3) as i16));
                     }
                 }
+            },
+            Event::Mouse(event) => {
+                if let Some(cell) = mouse::left_click(&event).and_then(|p| self.cell_at(p)) {
+                    self.pick_cell(cell);
+                }
+            }
+            Event::Server(ServerMessage::State(game @ Game::TicTacToe(_))) => {
+                self.show_state(game);
+            }
+            Event::Response(id, response) if self.pending.finish(id) => match response {
+                Response::State(game) | Response::Acted(game) => self.show_state(game),
+                Response::LegalActions(actions) => self.show_legal_moves(actions),
                 _ => {}
             },
+            Event::Failure(id, error) if self.pending.finish(id) => {
+                self.status = error.to_string();
+            }
+            Event::Server(ServerMessage::Error(error)) => {
+                self.status = error;
+            }
             _ => {}
         }
 
         Action::None
     }
 }
+
+fn token_name(token: BoardCell) -> &'static str {
+    match token {
+        BoardCell::X => "X",
+        BoardCell::Circle => "O",
+    }
+}
+
+/// A cell's coordinates as shown around the text board, e.g. "b2".
+fn cell_name((x, y): (i16, i16)) -> String {
+    format!("{}{}", COLUMNS[x as usize], y + 1)
+}
+
+/// The cell at a point on a text board drawn in `area`. See `board_text` for the layout: rows on
+/// every other line below the column letters, and cells 3 wide after the row number with a
+/// divider between them.
+fn text_cell(area: Rect, point: (u16, u16)) -> Option<(i16, i16)> {
+    let (column, line) = mouse::inner_point(area, point)?;
+    let column = column.checked_sub(2)?;
+    if line % 2 == 0 || line > 5 || column % 4 == 3 || column / 4 > 2 {
+        return None;
+    }
+
+    Some(((column / 4) as i16, 2 - (line / 2) as i16))
+}
+
+/// The cell at a point on a canvas board drawn in `area`.
+fn canvas_cell(area: Rect, point: (u16, u16)) -> Option<(i16, i16)> {
+    let (x, y) = mouse::canvas_point(area, BOARD_BOUNDS, BOARD_BOUNDS, point)?;
+    let cell = |v: f64| ((v - 1.0) / 25.0).floor().max(0.0).min(2.0) as i16;
+
+    Some((cell(x), cell(y)))
+}
+
+#[test]
+fn test_text_cell() {
+    // The board's block is at the top left, so the text starts at (1, 1).
+    let area = Rect::new(0, 0, 16, 8);
+
+    assert_eq!(text_cell(area, (3, 2)), Some((0, 2)));
+    assert_eq!(text_cell(area, (5, 2)), Some((0, 2)));
+    assert_eq!(text_cell(area, (7, 4)), Some((1, 1)));
+    assert_eq!(text_cell(area, (13, 6)), Some((2, 0)));
+
+    // The column letters, row numbers, dividers and borders aren't cells.
+    assert_eq!(text_cell(area, (3, 1)), None);
+    assert_eq!(text_cell(area, (1, 2)), None);
+    assert_eq!(text_cell(area, (6, 2)), None);
+    assert_eq!(text_cell(area, (3, 3)), None);
+    assert_eq!(text_cell(area, (0, 2)), None);
+    assert_eq!(text_cell(area, (3, 7)), None);
+    assert_eq!(text_cell(area, (14, 2)), None);
+    assert_eq!(text_cell(area, (20, 2)), None);
+}
+
+#[test]
+fn test_canvas_cell() {
+    let area = Rect::new(2, 1, 26, 14);
+
+    // The board's y axis points up, so the bottom left cell is (0, 0).
+    assert_eq!(canvas_cell(area, (3, 13)), Some((0, 0)));
+    assert_eq!(canvas_cell(area, (26, 2)), Some((2, 2)));
+    assert_eq!(canvas_cell(area, (14, 7)), Some((1, 1)));
+
+    assert_eq!(canvas_cell(area, (2, 7)), None);
+    assert_eq!(canvas_cell(area, (14, 1)), None);
+    assert_eq!(canvas_cell(area, (40, 7)), None);
+}
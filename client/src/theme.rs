@@ -0,0 +1,286 @@
+//! The styles screens draw with, by what they're for rather than by colour.
+//!
+//! The config picks one of the built-in themes and can recolour any of its roles, e.g.
+//!
+//! ```json
+//! {
+//!     "theme": "light",
+//!     "colors": { "selection": "dark_gray", "error": "#d70000" }
+//! }
+//! ```
+//!
+//! A colour is one of the terminal's named colours, like `red` or `light_blue`, a `#rrggbb` hex
+//! colour, or a number from the terminal's 256 colour palette. It only replaces the text colour,
+//! so a role keeps its background and any bold or underlining.
+
+use std::collections::HashMap;
+use std::env;
+
+use tui::style::{Color, Modifier, Style};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Plain text, like the options in a list.
+    pub text: Style,
+    /// Instructions and what's going on, like whose turn it is.
+    pub status: Style,
+    /// The border of the field being edited.
+    pub focus: Style,
+    /// The option picked in a list or set of tabs.
+    pub selection: Style,
+    /// Waiting on the server.
+    pub busy: Style,
+    pub error: Style,
+    /// Board cells the player can move to.
+    pub valid: Style,
+    /// Board cells the player can't move to.
+    pub invalid: Style,
+    pub own_token: Style,
+    pub opponent_token: Style,
+}
+
+impl Theme {
+    /// The names of the built-in themes, for the `theme` setting in the config.
+    pub const NAMES: [&'static str; 4] = ["default", "light", "high_contrast", "no_color"];
+
+    /// The names of the roles, for the `colors` setting in the config.
+    pub const ROLES: [&'static str; 10] = [
+        "text",
+        "status",
+        "focus",
+        "selection",
+        "busy",
+        "error",
+        "valid",
+        "invalid",
+        "own_token",
+        "opponent_token",
+    ];
+
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "light" => Some(Self::light()),
+            "high_contrast" => Some(Self::high_contrast()),
+            "no_color" => Some(Self::no_color()),
+            _ => None,
+        }
+    }
+
+    /// The theme named in the config with its `colors` applied, unless `NO_COLOR` is set, see
+    /// https://no-color.org.
+    pub fn load(name: Option<&str>, colors: &HashMap<String, String>) -> Result<Self, String> {
+        if env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty()) {
+            return Ok(Self::no_color());
+        }
+
+        let theme = match name {
+            Some(name) => Self::named(name).ok_or_else(|| {
+                format!(
+                    "Unknown theme {:?}, expected one of {}",
+                    name,
+                    Self::NAMES.join(", ")
+                )
+            })?,
+            None => Self::default(),
+        };
+
+        theme.recolor(colors)
+    }
+
+    /// This theme with the text colour of some of its roles replaced, by role name.
+    pub fn recolor(mut self, colors: &HashMap<String, String>) -> Result<Self, String> {
+        for (role, color) in colors {
+            let parsed = parse_color(color)
+                .ok_or_else(|| format!("Unknown colour {:?} for {:?}", color, role))?;
+
+            let style = self.role_mut(role).ok_or_else(|| {
+                format!(
+                    "Unknown theme role {:?}, expected one of {}",
+                    role,
+                    Self::ROLES.join(", ")
+                )
+            })?;
+
+            *style = style.fg(parsed);
+        }
+
+        Ok(self)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Style> {
+        Some(match role {
+            "text" => &mut self.text,
+            "status" => &mut self.status,
+            "focus" => &mut self.focus,
+            "selection" => &mut self.selection,
+            "busy" => &mut self.busy,
+            "error" => &mut self.error,
+            "valid" => &mut self.valid,
+            "invalid" => &mut self.invalid,
+            "own_token" => &mut self.own_token,
+            "opponent_token" => &mut self.opponent_token,
+            _ => return None,
+        })
+    }
+
+    /// For terminals with a light background.
+    pub fn light() -> Self {
+        Self {
+            text: Style::default().fg(Color::Black),
+            status: Style::default().fg(Color::Blue),
+            focus: Style::default().fg(Color::Magenta),
+            selection: Style::default()
+                .fg(Color::Magenta)
+                .modifier(Modifier::UNDERLINED),
+            busy: Style::default().fg(Color::Magenta),
+            error: Style::default().fg(Color::Red),
+            valid: Style::default().fg(Color::Black),
+            invalid: Style::default().fg(Color::Gray),
+            own_token: Style::default().fg(Color::Blue),
+            opponent_token: Style::default().fg(Color::Red),
+        }
+    }
+
+    /// Bright colours and bold text, for telling things apart at a glance.
+    pub fn high_contrast() -> Self {
+        Self {
+            text: Style::default().fg(Color::White),
+            status: Style::default()
+                .fg(Color::LightCyan)
+                .modifier(Modifier::BOLD),
+            focus: Style::default().fg(Color::LightYellow),
+            selection: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .modifier(Modifier::BOLD),
+            busy: Style::default()
+                .fg(Color::LightYellow)
+                .modifier(Modifier::BOLD),
+            error: Style::default()
+                .fg(Color::LightRed)
+                .modifier(Modifier::BOLD),
+            valid: Style::default().fg(Color::White),
+            invalid: Style::default().fg(Color::DarkGray),
+            own_token: Style::default().fg(Color::LightCyan),
+            opponent_token: Style::default().fg(Color::LightMagenta),
+        }
+    }
+
+    /// The terminal's own colours, telling things apart with bold, reversed and underlined text
+    /// instead.
+    pub fn no_color() -> Self {
+        Self {
+            text: Style::default(),
+            status: Style::default(),
+            focus: Style::default().modifier(Modifier::BOLD),
+            selection: Style::default().modifier(Modifier::REVERSED),
+            busy: Style::default(),
+            error: Style::default().modifier(Modifier::BOLD),
+            valid: Style::default().modifier(Modifier::BOLD),
+            invalid: Style::default(),
+            own_token: Style::default().modifier(Modifier::UNDERLINED),
+            opponent_token: Style::default(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            text: Style::default().fg(Color::White),
+            status: Style::default().fg(Color::Blue),
+            focus: Style::default().fg(Color::Cyan),
+            selection: Style::default()
+                .fg(Color::Green)
+                .modifier(Modifier::UNDERLINED),
+            busy: Style::default().fg(Color::Yellow),
+            error: Style::default().fg(Color::Red),
+            valid: Style::default().fg(Color::White),
+            invalid: Style::default().fg(Color::DarkGray),
+            own_token: Style::default().fg(Color::Blue),
+            opponent_token: Style::default().fg(Color::Red),
+        }
+    }
+}
+
+/// A colour as written in the config: a name, `#rrggbb`, or a palette number.
+fn parse_color(name: &str) -> Option<Color> {
+    if name.starts_with('#') {
+        let hex = &name[1..];
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+        return if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+        } else {
+            None
+        };
+    }
+
+    if let Ok(index) = name.parse() {
+        return Some(Color::Indexed(index));
+    }
+
+    Some(match name {
+        "reset" => Color::Reset,
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" => Color::Gray,
+        "dark_gray" => Color::DarkGray,
+        "light_red" => Color::LightRed,
+        "light_green" => Color::LightGreen,
+        "light_yellow" => Color::LightYellow,
+        "light_blue" => Color::LightBlue,
+        "light_magenta" => Color::LightMagenta,
+        "light_cyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    })
+}
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(parse_color("red"), Some(Color::Red));
+    assert_eq!(parse_color("light_blue"), Some(Color::LightBlue));
+    assert_eq!(parse_color("#d70080"), Some(Color::Rgb(0xd7, 0x00, 0x80)));
+    assert_eq!(parse_color("208"), Some(Color::Indexed(208)));
+
+    assert_eq!(parse_color("Red"), None);
+    assert_eq!(parse_color("#d700"), None);
+    assert_eq!(parse_color("#+d7008"), None);
+    assert_eq!(parse_color("256"), None);
+}
+
+#[test]
+fn test_recolor() {
+    let mut colors = HashMap::new();
+    colors.insert("selection".to_owned(), "dark_gray".to_owned());
+    colors.insert("error".to_owned(), "#d70000".to_owned());
+
+    let theme = Theme::default().recolor(&colors).unwrap();
+
+    assert_eq!(
+        theme.selection,
+        Style::default()
+            .fg(Color::DarkGray)
+            .modifier(Modifier::UNDERLINED)
+    );
+    assert_eq!(theme.error, Style::default().fg(Color::Rgb(0xd7, 0, 0)));
+    assert_eq!(theme.text, Theme::default().text);
+}
+
+#[test]
+fn test_recolor_errors() {
+    let mut colors = HashMap::new();
+    colors.insert("border".to_owned(), "red".to_owned());
+    assert!(Theme::default().recolor(&colors).is_err());
+
+    let mut colors = HashMap::new();
+    colors.insert("text".to_owned(), "reddish".to_owned());
+    assert!(Theme::default().recolor(&colors).is_err());
+}
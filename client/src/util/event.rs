@@ -3,17 +3,46 @@ use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 
-use termion::event::Key;
+use termion::event::{Event as TermEvent, Key, MouseEvent};
 use termion::input::TermRead;
 
+use common::ServerMessage;
+
+use crate::keymap::Command;
+use crate::util::network::{Fault, RequestId, Response};
+
 pub enum Event {
+    /// A key that isn't bound to a command, or is being typed as text.
     Input(Key),
+
+    /// A key bound to a command in the keymap. The `StateManager` turns `Input`s into these.
+    Command(Command),
+
+    /// Clicks and drags, with termion's 1-based coordinates. See `util::mouse`.
+    Mouse(MouseEvent),
+
     Tick,
+
+    /// Pushed by the server over a lobby connection.
+    Server(ServerMessage),
+
+    /// A request from `Network::send` succeeded.
+    Response(RequestId, Response),
+
+    /// A request from `Network::send` failed.
+    Failure(RequestId, client_sdk::Error),
+
+    /// A request from `Network::send` couldn't get through to the server. The `StateManager` asks
+    /// whether to retry it, and only passes it on as a `Failure` when giving up.
+    Fault(Fault),
 }
 
+pub type EventSender = mpsc::Sender<Event>;
+
 /// A small event handler that wrap termion input and tick events. Each event
 /// type is handled in its own thread and returned to a common `Receiver`
 pub struct Events {
+    tx: EventSender,
     rx: mpsc::Receiver<Event>,
     input_handle: thread::JoinHandle<()>,
     tick_handle: thread::JoinHandle<()>,
@@ -21,14 +50,12 @@ pub struct Events {
 
 #[derive(Debug, Clone, Copy)]
 pub struct Config {
-    pub exit_key: Key,
     pub tick_rate: Duration,
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
-            exit_key: Key::Char('q'),
             tick_rate: Duration::from_millis(250),
         }
     }
@@ -45,17 +72,19 @@ impl Events {
             let tx = tx.clone();
             thread::spawn(move || {
                 let stdin = io::stdin();
-                for evt in stdin.keys() {
+                for evt in stdin.events() {
                     match evt {
-                        Ok(key) => {
+                        Ok(TermEvent::Key(key)) => {
                             if let Err(_) = tx.send(Event::Input(key)) {
                                 return;
                             }
-                            if key == config.exit_key {
+                        }
+                        Ok(TermEvent::Mouse(mouse)) => {
+                            if let Err(_) = tx.send(Event::Mouse(mouse)) {
                                 return;
                             }
                         }
-                        Err(_) => {}
+                        _ => {}
                     }
                 }
             })
@@ -64,19 +93,25 @@ impl Events {
             let tx = tx.clone();
             thread::spawn(move || {
                 let tx = tx.clone();
-                loop {
-                    tx.send(Event::Tick).unwrap();
+                // Stops once the event loop has gone.
+                while tx.send(Event::Tick).is_ok() {
                     thread::sleep(config.tick_rate);
                 }
             })
         };
         Events {
+            tx,
             rx,
             input_handle,
             tick_handle,
         }
     }
 
+    /// For feeding events into the channel from elsewhere, e.g. lobby connections.
+    pub fn sender(&self) -> EventSender {
+        self.tx.clone()
+    }
+
     pub fn next(&self) -> Result<Event, mpsc::RecvError> {
         self.rx.recv()
     }
@@ -0,0 +1,56 @@
+//! Details of what went wrong, kept in a file since the terminal belongs to the UI.
+
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Debug)]
+pub struct Log {
+    path: Option<PathBuf>,
+}
+
+impl Log {
+    /// `$XDG_STATE_HOME/game-client/client.log`, falling back to `~/.local/state`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_STATE_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?)
+                .join(".local")
+                .join("state"),
+        };
+
+        Some(base.join("game-client").join("client.log"))
+    }
+
+    /// Log to `path`, or nowhere if there isn't one.
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Append `message`, stamped with the time in seconds. Failing to log is never worth
+    /// interrupting the game over, so that's ignored.
+    pub fn write(&self, message: &str) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+            let _ = writeln!(file, "[{}] {}", time, message);
+        }
+    }
+}
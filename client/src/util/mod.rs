@@ -1,4 +1,7 @@
 pub mod event;
+pub mod log;
+pub mod mouse;
+pub mod network;
 
 use rand::distributions::{Distribution, Uniform};
 use rand::rngs::ThreadRng;
@@ -0,0 +1,188 @@
+//! Working out what was clicked on. Screens remember the `Rect`s they last rendered widgets into
+//! and hit-test clicks against them.
+//!
+//! Clicking something picks it, clicking it again when it's already picked is the same as
+//! pressing Enter on it.
+
+use termion::event::{MouseButton, MouseEvent};
+use tui::layout::Rect;
+
+/// The cell a left click was on, 0-based like tui's `Rect`s rather than termion's 1-based ones.
+pub fn left_click(event: &MouseEvent) -> Option<(u16, u16)> {
+    match *event {
+        MouseEvent::Press(MouseButton::Left, x, y) => {
+            Some((x.saturating_sub(1), y.saturating_sub(1)))
+        }
+        _ => None,
+    }
+}
+
+fn contains(area: Rect, (x, y): (u16, u16)) -> bool {
+    x >= area.left() && x < area.right() && y >= area.top() && y < area.bottom()
+}
+
+/// The area inside a `Block` with all borders.
+fn inner(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    }
+}
+
+/// Where the point is relative to the top left inside a `Block` with all borders rendered in
+/// `area`, for widgets laid out by hand.
+pub fn inner_point(area: Rect, point: (u16, u16)) -> Option<(u16, u16)> {
+    let inner = inner(area);
+    if contains(inner, point) {
+        Some((point.0 - inner.x, point.1 - inner.y))
+    } else {
+        None
+    }
+}
+
+/// Which of `len` rows of a bordered list or table rendered in `area` the point is on. Rows start
+/// `offset` lines below the top border, e.g. past a table's header.
+pub fn list_row(area: Rect, offset: u16, len: usize, point: (u16, u16)) -> Option<usize> {
+    let inner = inner(area);
+    if !contains(inner, point) || point.1 < inner.y + offset {
+        return None;
+    }
+
+    let row = (point.1 - inner.y - offset) as usize;
+    if row < len {
+        Some(row)
+    } else {
+        None
+    }
+}
+
+/// Which of `titles` of a borderless `Tabs` rendered in `area` the point is on. The spaces around
+/// each title and the divider after it count as part of it.
+pub fn tab(area: Rect, titles: &[&str], point: (u16, u16)) -> Option<usize> {
+    if !contains(area, point) || point.1 != area.y {
+        return None;
+    }
+
+    let mut start = area.x;
+    for (i, title) in titles.iter().enumerate() {
+        let end = start + 1 + title.chars().count() as u16 + 1;
+        if point.0 >= start && point.0 <= end {
+            return Some(i);
+        }
+
+        start = end + 1;
+    }
+
+    None
+}
+
+/// Where the point is in the coordinates of a bordered `Canvas` rendered in `area` with
+/// `x_bounds` and `y_bounds`, taking the middle of the terminal cell.
+pub fn canvas_point(
+    area: Rect,
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+    point: (u16, u16),
+) -> Option<(f64, f64)> {
+    let inner = inner(area);
+    if !contains(inner, point) {
+        return None;
+    }
+
+    let across = (f64::from(point.0 - inner.x) + 0.5) / f64::from(inner.width);
+    // The canvas' y axis points up, the terminal's down.
+    let up = 1.0 - (f64::from(point.1 - inner.y) + 0.5) / f64::from(inner.height);
+
+    Some((
+        x_bounds[0] + across * (x_bounds[1] - x_bounds[0]),
+        y_bounds[0] + up * (y_bounds[1] - y_bounds[0]),
+    ))
+}
+
+#[test]
+fn test_left_click() {
+    assert_eq!(
+        left_click(&MouseEvent::Press(MouseButton::Left, 1, 1)),
+        Some((0, 0))
+    );
+    assert_eq!(
+        left_click(&MouseEvent::Press(MouseButton::Left, 10, 5)),
+        Some((9, 4))
+    );
+    assert_eq!(
+        left_click(&MouseEvent::Press(MouseButton::Right, 10, 5)),
+        None
+    );
+    assert_eq!(left_click(&MouseEvent::Release(10, 5)), None);
+}
+
+#[test]
+fn test_inner_point() {
+    let area = Rect::new(2, 3, 5, 4);
+
+    assert_eq!(inner_point(area, (3, 4)), Some((0, 0)));
+    assert_eq!(inner_point(area, (5, 5)), Some((2, 1)));
+
+    // On the borders.
+    assert_eq!(inner_point(area, (2, 4)), None);
+    assert_eq!(inner_point(area, (6, 4)), None);
+    assert_eq!(inner_point(area, (3, 3)), None);
+    assert_eq!(inner_point(area, (3, 6)), None);
+}
+
+#[test]
+fn test_list_row() {
+    // A table like the lobby browser's: a border, then a header and a blank line.
+    let area = Rect::new(0, 2, 20, 10);
+
+    assert_eq!(list_row(area, 2, 3, (5, 5)), Some(0));
+    assert_eq!(list_row(area, 2, 3, (18, 7)), Some(2));
+
+    // The border, header and blank line above the rows.
+    assert_eq!(list_row(area, 2, 3, (5, 2)), None);
+    assert_eq!(list_row(area, 2, 3, (5, 3)), None);
+    assert_eq!(list_row(area, 2, 3, (5, 4)), None);
+
+    // Past the last row, on the side and bottom borders, and outside the table.
+    assert_eq!(list_row(area, 2, 3, (5, 8)), None);
+    assert_eq!(list_row(area, 2, 3, (0, 5)), None);
+    assert_eq!(list_row(area, 2, 3, (19, 5)), None);
+    assert_eq!(list_row(area, 2, 20, (5, 11)), None);
+    assert_eq!(list_row(area, 2, 3, (5, 1)), None);
+    assert_eq!(list_row(area, 2, 3, (25, 5)), None);
+
+    assert_eq!(list_row(area, 0, 3, (5, 3)), Some(0));
+}
+
+#[test]
+fn test_tab() {
+    let area = Rect::new(0, 0, 30, 1);
+    let titles = ["ab", "cde"];
+
+    assert_eq!(tab(area, &titles, (0, 0)), Some(0));
+    assert_eq!(tab(area, &titles, (4, 0)), Some(0));
+    assert_eq!(tab(area, &titles, (5, 0)), Some(1));
+    assert_eq!(tab(area, &titles, (10, 0)), Some(1));
+    assert_eq!(tab(area, &titles, (11, 0)), None);
+    assert_eq!(tab(area, &titles, (2, 1)), None);
+}
+
+#[test]
+fn test_canvas_point() {
+    // 8 cells across inside the borders, so each is 10 wide.
+    let area = Rect::new(0, 0, 10, 10);
+    let bounds = [0.0, 80.0];
+
+    assert_eq!(
+        canvas_point(area, bounds, bounds, (1, 1)),
+        Some((5.0, 75.0))
+    );
+    assert_eq!(
+        canvas_point(area, bounds, bounds, (8, 8)),
+        Some((75.0, 5.0))
+    );
+    assert_eq!(canvas_point(area, bounds, bounds, (0, 5)), None);
+    assert_eq!(canvas_point(area, bounds, bounds, (5, 9)), None);
+}
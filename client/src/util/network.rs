@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_std::task;
+use uuid::Uuid;
+
+use client_sdk::{Error, GameClient, Watch};
+use common::{Action, CreateLobbyRequest, Game, JoinResponse, Lobby, LobbyQuery, VersionInfo};
+
+use crate::util::event::{Event, EventSender};
+
+/// Tells responses apart, so a screen only handles the ones to its own requests.
+pub type RequestId = u64;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug)]
+pub enum Request {
+    Version,
+    Lobbies(LobbyQuery),
+    CreateLobby(CreateLobbyRequest),
+    Join(String),
+    State(String),
+    LegalActions(String, Uuid),
+    Act(String, Action),
+}
+
+pub enum Response {
+    Version(VersionInfo),
+    Lobbies(Vec<Lobby>),
+    /// The new lobby's ID
+    Created(String),
+    Joined(JoinResponse),
+    State(Game),
+    LegalActions(Vec<Action>),
+    Acted(Game),
+}
+
+async fn perform(client: &GameClient, request: Request) -> Result<Response, Error> {
+    Ok(match request {
+        Request::Version => Response::Version(client.version().await?),
+        Request::Lobbies(query) => Response::Lobbies(client.find_lobbies(&query).await?),
+        Request::CreateLobby(request) => Response::Created(client.create_lobby(&request).await?.id),
+        Request::Join(lobby) => Response::Joined(client.join(&lobby).await?),
+        Request::State(lobby) => Response::State(client.state(&lobby).await?),
+        Request::LegalActions(lobby, player) => {
+            Response::LegalActions(client.legal_actions(&lobby, player).await?)
+        }
+        Request::Act(lobby, action) => Response::Acted(client.act(&lobby, &action).await?),
+    })
+}
+
+/// Runs requests to the server in the background, so the UI never waits on it. Each one ends in
+/// an `Event::Response`, `Event::Failure` or `Event::Fault` carrying the id `send` returned.
+#[derive(Clone)]
+pub struct Network {
+    client: GameClient,
+    events: EventSender,
+}
+
+impl Network {
+    pub fn new(client: GameClient, events: EventSender) -> Self {
+        Self { client, events }
+    }
+
+    pub fn client(&self) -> &GameClient {
+        &self.client
+    }
+
+    pub fn send(&self, request: Request) -> RequestId {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        self.spawn(id, request);
+        id
+    }
+
+    fn spawn(&self, id: RequestId, request: Request) {
+        let network = self.clone();
+        let events = self.events.clone();
+
+        task::spawn(async move {
+            let event = match perform(&network.client, request.clone()).await {
+                Ok(response) => Event::Response(id, response),
+                Err(error) if is_fault(&error) => Event::Fault(Fault {
+                    id,
+                    error,
+                    request,
+                    network,
+                }),
+                Err(error) => Event::Failure(id, error),
+            };
+
+            let _ = events.send(event);
+        });
+    }
+
+    /// Follow `lobby`, with everything the server pushes arriving as `Event::Server`.
+    pub fn watch(&self, lobby: &str) -> Watch {
+        let events = self.events.clone();
+        self.client.watch(lobby, move |message| {
+            events.send(Event::Server(message)).is_ok()
+        })
+    }
+}
+
+/// Whether `error` means the server couldn't be talked to at all, rather than it turning the
+/// request down. A response that can't be decoded means the client and server disagree on the
+/// protocol, which retrying won't fix.
+fn is_fault(error: &Error) -> bool {
+    match error {
+        Error::Http(_) => true,
+        Error::Status(status) => *status >= 500,
+        Error::Api(_) | Error::Decode(_) | Error::Incompatible(_) => false,
+    }
+}
+
+/// A request that failed because of the connection or the server rather than the request itself,
+/// which may go away if it's tried again.
+pub struct Fault {
+    pub id: RequestId,
+    pub error: Error,
+    request: Request,
+    network: Network,
+}
+
+impl Fault {
+    /// Send the request again under the same id, so whoever is waiting on it still gets the answer.
+    pub fn retry(self) {
+        self.network.spawn(self.id, self.request);
+    }
+
+    /// Give up, handing the failure to whoever is waiting on it.
+    pub fn into_failure(self) -> Event {
+        Event::Failure(self.id, self.error)
+    }
+
+    /// What failed and why, for the log.
+    pub fn details(&self) -> String {
+        format!(
+            "{:?} to {} failed: {:?}",
+            self.request,
+            self.network.client.server(),
+            self.error
+        )
+    }
+}
+
+/// The requests a screen is waiting on.
+#[derive(Default)]
+pub struct Pending {
+    ids: HashSet<RequestId>,
+}
+
+impl Pending {
+    pub fn add(&mut self, id: RequestId) {
+        self.ids.insert(id);
+    }
+
+    /// Stop waiting on `id`, returning whether it was one of ours.
+    pub fn finish(&mut self, id: RequestId) -> bool {
+        self.ids.remove(&id)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        !self.ids.is_empty()
+    }
+}
+
+/// A frame of a spinner for showing a request is in flight. Screens redraw on every tick, which
+/// animates it.
+pub fn spinner() -> char {
+    const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+
+    FRAMES[(millis / 250 % FRAMES.len() as u128) as usize]
+}
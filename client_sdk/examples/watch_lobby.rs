@@ -0,0 +1,43 @@
+//! Prints everything that happens in a lobby until it's over. `GET /api/v1/lobbies` lists the
+//! lobby IDs.
+//!
+//! `cargo run -p client_sdk --example watch_lobby -- localhost:8000 <lobby ID>`
+
+use std::sync::mpsc;
+
+use client_sdk::common::ServerMessage;
+use client_sdk::GameClient;
+
+#[async_std::main]
+async fn main() -> Result<(), client_sdk::Error> {
+    let mut args = std::env::args().skip(1);
+    let server = args
+        .next()
+        .unwrap_or_else(|| String::from("localhost:8000"));
+    let lobby = match args.next() {
+        Some(lobby) => lobby,
+        None => {
+            eprintln!("usage: watch_lobby <server> <lobby ID>");
+            return Ok(());
+        }
+    };
+
+    let client = GameClient::new(&server);
+    let version = client.version().await?;
+    println!("{} speaks protocol {}", server, version.protocol);
+
+    println!("{:?}", client.state(&lobby).await?);
+
+    let (sender, receiver) = mpsc::channel();
+    let _watch = client.watch(&lobby, move |message| sender.send(message).is_ok());
+
+    for message in receiver {
+        println!("{:?}", message);
+
+        if let ServerMessage::Error(_) = message {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! A typed client for the game server's HTTP API.
+//!
+//! [`GameClient`] wraps each endpoint in an async method returning the `common` types, with the
+//! server's failures decoded into [`ApiError`]s. [`GameClient::watch`] follows a lobby as it
+//! changes.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+use common::error::{ApiError, ErrorResponse};
+use common::tictactoe_solver::Analysis;
+use common::{
+    Action, AddBotRequest, AnalysisRequest, BotCredentials, BotDifficulty, CreateLobbyRequest,
+    CreateLobbyResponse, Game, JoinResponse, Lobby, LobbyQuery, RegisterBotRequest, ServerMessage,
+    TurnEvent, VersionInfo, VersionedGame,
+};
+
+mod watch;
+
+pub use common;
+pub use watch::Watch;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The server couldn't be reached.
+    Http(surf::Exception),
+
+    /// The server rejected the request.
+    Api(ApiError),
+
+    /// The server failed with a status but no error body, e.g. from a proxy in front of it.
+    Status(u16),
+
+    /// The response didn't match the type this client expects.
+    Decode(serde_json::Error),
+
+    /// The server speaks a protocol this client doesn't, found by [`GameClient::version`].
+    Incompatible(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "couldn't reach the server: {}", e),
+            Error::Api(e) => write!(f, "{}", e),
+            Error::Status(status) => write!(f, "the server failed with status {}", status),
+            Error::Decode(e) => write!(f, "unexpected response from the server: {}", e),
+            Error::Incompatible(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ApiError> for Error {
+    fn from(error: ApiError) -> Self {
+        Error::Api(error)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Read `response` as a `T`, or as the [`ErrorResponse`] the server sends alongside a failing
+/// status.
+async fn receive<T: DeserializeOwned>(
+    response: std::result::Result<surf::Response, surf::Exception>,
+) -> Result<T> {
+    let mut response = response.map_err(Error::Http)?;
+    let body = response
+        .body_bytes()
+        .await
+        .map_err(|e| Error::Http(e.into()))?;
+
+    if !response.status().is_success() {
+        return match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(response) => Err(Error::Api(response.error)),
+            Err(_) => Err(Error::Status(response.status().as_u16())),
+        };
+    }
+
+    serde_json::from_slice(&body).map_err(Error::Decode)
+}
+
+/// `segment` made safe to put in a URL path, percent-encoding everything but unreserved
+/// characters. Lobby IDs are generated by the server, so don't count on them being URL-safe.
+fn encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Talks to one server. Cheap to clone, each request is independent.
+#[derive(Clone, Debug)]
+pub struct GameClient {
+    server: String,
+}
+
+impl GameClient {
+    /// A client for the server at `server`, e.g. `localhost:8000`. A scheme and trailing slashes
+    /// are dropped, so `http://localhost:8000/` works too, but requests are always plain HTTP.
+    pub fn new(server: &str) -> Self {
+        let server = server.trim();
+        let server = match server.find("://") {
+            Some(i) => &server[i + 3..],
+            None => server,
+        };
+
+        Self {
+            server: server.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("http://{}/api/v1{}", self.server, path)
+    }
+
+    /// The URL of `path` under the lobby with ID `lobby`.
+    fn lobby_url(&self, lobby: &str, path: &str) -> String {
+        self.url(&format!("/lobbies/{}{}", encode(lobby), path))
+    }
+
+    /// What the server speaks. Fails with [`Error::Incompatible`] if that isn't the protocol this
+    /// client was built with, so call this before anything else.
+    pub async fn version(&self) -> Result<VersionInfo> {
+        let body: serde_json::Value = match receive(surf::get(self.url("/version")).await).await {
+            Ok(body) => body,
+            // Servers from before the handshake don't have the endpoint at all.
+            Err(Error::Api(ApiError::NotFound)) | Err(Error::Status(404)) => {
+                return Err(Error::Incompatible(String::from(
+                    "The server is too old for this client, it doesn't report a protocol version.",
+                )))
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Check the protocol before decoding the rest, which may list game types from a newer one.
+        let protocol = body["protocol"].as_u64().unwrap_or(0);
+
+        if let Some(reason) = common::protocol_incompatibility(protocol as u32) {
+            return Err(Error::Incompatible(reason));
+        }
+
+        serde_json::from_value(body).map_err(Error::Decode)
+    }
+
+    /// Every lobby, in order of name.
+    pub async fn lobbies(&self) -> Result<Vec<Lobby>> {
+        receive(surf::get(self.url("/lobbies")).await).await
+    }
+
+    /// The lobbies matching `query`, in order of name. Servers without the `lobby_query` feature
+    /// ignore the query and list every lobby.
+    pub async fn find_lobbies(&self, query: &LobbyQuery) -> Result<Vec<Lobby>> {
+        let request = surf::get(self.url("/lobbies"))
+            .set_query(query)
+            .map_err(|e| Error::Http(e.into()))?;
+
+        receive(request.await).await
+    }
+
+    /// Create a lobby, getting the ID the other methods take. Fails with
+    /// [`ApiError::LobbyNameTaken`] if there's already an open lobby with the same name.
+    pub async fn create_lobby(&self, request: &CreateLobbyRequest) -> Result<CreateLobbyResponse> {
+        let request = surf::post(self.url("/lobbies"))
+            .body_json(request)
+            .map_err(Error::Decode)?;
+
+        receive(request.await).await
+    }
+
+    /// Take a seat in `lobby`, getting the player ID to act as.
+    pub async fn join(&self, lobby: &str) -> Result<JoinResponse> {
+        let url = self.lobby_url(lobby, "/join");
+        receive(surf::post(url).await).await
+    }
+
+    /// The game in `lobby` right now.
+    pub async fn state(&self, lobby: &str) -> Result<Game> {
+        let url = self.lobby_url(lobby, "/state");
+        let state: VersionedGame = receive(surf::get(url).await).await?;
+        Ok(state.game)
+    }
+
+    /// Wait for the game in `lobby` to change past version `since`, for up to `timeout` seconds.
+    /// Gives the current game if it doesn't.
+    pub async fn wait_for_state(
+        &self,
+        lobby: &str,
+        since: u64,
+        timeout: u64,
+    ) -> Result<VersionedGame> {
+        let url = self.lobby_url(
+            lobby,
+            &format!("/state?since={}&timeout={}", since, timeout),
+        );
+
+        receive(surf::get(url).await).await
+    }
+
+    /// The actions `player` can currently take in `lobby`.
+    pub async fn legal_actions(&self, lobby: &str, player: Uuid) -> Result<Vec<Action>> {
+        let url = self.lobby_url(lobby, &format!("/legal-actions?player={}", player));
+        receive(surf::get(url).await).await
+    }
+
+    /// Perform `action` in `lobby`, getting the game after it.
+    pub async fn act(&self, lobby: &str, action: &Action) -> Result<Game> {
+        let request = surf::post(self.lobby_url(lobby, "/action"))
+            .body_json(action)
+            .map_err(Error::Decode)?;
+
+        receive(request.await).await
+    }
+
+    /// Seat a server-side bot in `lobby`.
+    pub async fn add_bot(&self, lobby: &str, difficulty: BotDifficulty) -> Result<Game> {
+        let request = surf::post(self.lobby_url(lobby, "/bots"))
+            .body_json(&AddBotRequest { difficulty })
+            .map_err(Error::Decode)?;
+
+        receive(request.await).await
+    }
+
+    /// Solve a tic-tac-toe position on the server.
+    pub async fn analyse_tictactoe(&self, request: &AnalysisRequest) -> Result<Analysis> {
+        let request = surf::post(self.url("/analysis/tictactoe"))
+            .body_json(request)
+            .map_err(Error::Decode)?;
+
+        receive(request.await).await
+    }
+
+    /// Register a bot under `name`, getting the credentials it authenticates with.
+    pub async fn register_bot(&self, name: &str) -> Result<BotCredentials> {
+        let request = surf::post(self.url("/bots/register"))
+            .body_json(&RegisterBotRequest {
+                name: name.to_owned(),
+            })
+            .map_err(Error::Decode)?;
+
+        receive(request.await).await
+    }
+
+    /// Join `lobby` as the bot with `credentials`, so its turns there show up in
+    /// [`GameClient::bot_events`].
+    pub async fn bot_join(
+        &self,
+        credentials: &BotCredentials,
+        lobby: &str,
+    ) -> Result<JoinResponse> {
+        let request = surf::post(self.url(&format!("/bots/lobbies/{}/join", encode(lobby))))
+            .set_header("X-Api-Key", &credentials.api_key);
+
+        receive(request.await).await
+    }
+
+    /// Wait up to `timeout` seconds for it to be the bot's turn in any of its lobbies.
+    pub async fn bot_events(
+        &self,
+        credentials: &BotCredentials,
+        timeout: u64,
+    ) -> Result<Vec<TurnEvent>> {
+        let request = surf::get(self.url(&format!("/bots/events?timeout={}", timeout)))
+            .set_header("X-Api-Key", &credentials.api_key);
+
+        receive(request.await).await
+    }
+
+    /// Follow `lobby` in the background, calling `on_message` with everything the server pushes
+    /// until it returns `false` or the [`Watch`] is dropped.
+    pub fn watch<F>(&self, lobby: &str, on_message: F) -> Watch
+    where
+        F: FnMut(ServerMessage) -> bool + Send + 'static,
+    {
+        Watch::open(self.clone(), lobby, on_message)
+    }
+}
+
+#[test]
+fn test_server_address() {
+    assert_eq!(GameClient::new("localhost:8000").server(), "localhost:8000");
+    assert_eq!(
+        GameClient::new("http://localhost:8000/").server(),
+        "localhost:8000"
+    );
+    assert_eq!(
+        GameClient::new(" https://[::1]:8000// ").server(),
+        "[::1]:8000"
+    );
+    assert_eq!(
+        GameClient::new("http://example.com/").url("/version"),
+        "http://example.com/api/v1/version"
+    );
+}
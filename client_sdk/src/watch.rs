@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use async_std::task;
+use parking_lot::Mutex;
+use ws::{CloseCode, Handler, Message};
+
+use common::ServerMessage;
+
+use crate::GameClient;
+
+/// How long each long-poll request waits on the server for the game to change, in seconds.
+const POLL_TIMEOUT: u64 = 30;
+
+type Callback = Arc<Mutex<dyn FnMut(ServerMessage) -> bool + Send>>;
+
+/// Follows a lobby, from [`GameClient::watch`].
+///
+/// Updates come over a WebSocket. If that can't be opened or drops, this falls back to
+/// long-polling the lobby's state instead. Everything is shut down when this is dropped.
+pub struct Watch {
+    out: Arc<Mutex<Option<ws::Sender>>>,
+    closed: Arc<AtomicBool>,
+}
+
+struct Watcher {
+    out: ws::Sender,
+    on_message: Callback,
+    closed: Arc<AtomicBool>,
+}
+
+impl Handler for Watcher {
+    fn on_message(&mut self, msg: Message) -> ws::Result<()> {
+        let message = match serde_json::from_str(msg.as_text()?) {
+            Ok(message) => message,
+            Err(e) => ServerMessage::Error(format!("bad message: {}", e)),
+        };
+
+        if !(self.on_message.lock())(message) {
+            self.closed.store(true, Ordering::SeqCst);
+            self.out.close(CloseCode::Normal)?;
+        }
+
+        Ok(())
+    }
+
+    /// Long-polling takes over from here. The default prints to stderr, which may be under a UI.
+    fn on_error(&mut self, _err: ws::Error) {}
+}
+
+/// Pass on each new version of the game until `closed` is set.
+async fn long_poll(
+    client: GameClient,
+    lobby: String,
+    on_message: Callback,
+    closed: Arc<AtomicBool>,
+) {
+    let mut version = None;
+
+    while !closed.load(Ordering::SeqCst) {
+        match client
+            .wait_for_state(&lobby, version.unwrap_or(0), POLL_TIMEOUT)
+            .await
+        {
+            Ok(update) => {
+                if version == Some(update.version) || closed.load(Ordering::SeqCst) {
+                    continue;
+                }
+
+                version = Some(update.version);
+
+                if !(on_message.lock())(ServerMessage::State(update.game)) {
+                    return;
+                }
+            }
+            Err(_) => task::sleep(Duration::from_secs(1)).await,
+        }
+    }
+}
+
+/// The host part of a server address like `localhost:8000`, `[::1]:8000` or `::1`, for reaching
+/// the WebSocket server on the same machine. IPv6 addresses keep or gain their brackets.
+fn host(server: &str) -> String {
+    let host = if server.starts_with('[') {
+        match server.find(']') {
+            Some(end) => &server[..=end],
+            None => server,
+        }
+    } else if server.matches(':').count() > 1 {
+        return format!("[{}]", server);
+    } else {
+        server.split(':').next().unwrap_or_default()
+    };
+
+    if host.is_empty() {
+        String::from("localhost")
+    } else {
+        host.to_owned()
+    }
+}
+
+impl Watch {
+    pub(crate) fn open<F>(client: GameClient, lobby: &str, on_message: F) -> Self
+    where
+        F: FnMut(ServerMessage) -> bool + Send + 'static,
+    {
+        let ws_url = format!(
+            "ws://{}:{}/lobbies/{}",
+            host(client.server()),
+            common::WEBSOCKET_PORT,
+            crate::encode(lobby)
+        );
+        let lobby = lobby.to_owned();
+
+        let on_message: Callback = Arc::new(Mutex::new(on_message));
+        let out = Arc::new(Mutex::new(None));
+        let closed = Arc::new(AtomicBool::new(false));
+
+        {
+            let out = out.clone();
+            let closed = closed.clone();
+
+            thread::spawn(move || {
+                let _ = ws::connect(ws_url, |sender: ws::Sender| {
+                    *out.lock() = Some(sender.clone());
+
+                    Watcher {
+                        out: sender,
+                        on_message: on_message.clone(),
+                        closed: closed.clone(),
+                    }
+                });
+
+                task::block_on(long_poll(client, lobby, on_message, closed));
+            });
+        }
+
+        Self { out, closed }
+    }
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+
+        if let Some(out) = self.out.lock().take() {
+            let _ = out.close(CloseCode::Normal);
+        }
+    }
+}
+
+#[test]
+fn test_host() {
+    assert_eq!(host("localhost:8000"), "localhost");
+    assert_eq!(host("example.com"), "example.com");
+    assert_eq!(host("[::1]:8000"), "[::1]");
+    assert_eq!(host("[2001:db8::1]"), "[2001:db8::1]");
+    assert_eq!(host("::1"), "[::1]");
+    assert_eq!(host(":8000"), "localhost");
+    assert_eq!(host(""), "localhost");
+}
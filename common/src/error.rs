@@ -0,0 +1,160 @@
+//! Errors the server responds with, as an [`ErrorResponse`] and a matching HTTP status.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{rockpaperscissors, tictactoe};
+
+/// Serialized with a machine-readable `code` in snake case, plus `details` for some codes, e.g.
+/// `{"code": "lobby_not_found"}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(tag = "code", content = "details", rename_all = "snake_case")]
+pub enum ApiError {
+    /// The request couldn't be understood, e.g. a malformed body or player ID.
+    BadRequest(String),
+
+    /// The body is JSON, but not the shape the endpoint expects.
+    InvalidBody,
+
+    /// A bot endpoint was called without a valid `X-Api-Key`.
+    Unauthorized,
+
+    /// There's no endpoint at the requested path.
+    NotFound,
+
+    LobbyNotFound,
+
+    /// A lobby that's open or in progress already has the name asked for.
+    LobbyNameTaken,
+
+    /// Every seat in the lobby is taken.
+    LobbyFull,
+
+    /// The lobby's game is finished or abandoned, so nothing more can happen in it.
+    LobbyClosed,
+
+    /// The action is for a different game than the one in the lobby.
+    WrongGameType,
+
+    InvalidTicTacToeAction(tictactoe::InvalidAction),
+    InvalidRockPaperScissorsAction(rockpaperscissors::InvalidAction),
+
+    /// Too many requests are already waiting for changes. Try again shortly.
+    Busy,
+}
+
+impl ApiError {
+    /// The HTTP status code to respond with.
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::BadRequest(_) => 400,
+            ApiError::Unauthorized => 401,
+            ApiError::NotFound | ApiError::LobbyNotFound => 404,
+            ApiError::LobbyNameTaken | ApiError::LobbyFull | ApiError::LobbyClosed => 409,
+            ApiError::InvalidBody | ApiError::WrongGameType => 422,
+            ApiError::InvalidTicTacToeAction(tictactoe::InvalidAction::PositionOutOfBounds) => 422,
+            ApiError::InvalidRockPaperScissorsAction(
+                rockpaperscissors::InvalidAction::UnknownPlayer,
+            ) => 422,
+            ApiError::InvalidTicTacToeAction(_) | ApiError::InvalidRockPaperScissorsAction(_) => {
+                409
+            }
+            ApiError::Busy => 503,
+        }
+    }
+}
+
+/// Messages fit for showing to players.
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use tictactoe::InvalidAction;
+
+        match self {
+            ApiError::BadRequest(reason) => write!(f, "Bad request: {}", reason),
+            ApiError::InvalidBody => write!(f, "The server didn't understand the request."),
+            ApiError::Unauthorized => write!(f, "Missing or invalid API key."),
+            ApiError::NotFound => write!(f, "The server doesn't know about that."),
+            ApiError::LobbyNotFound => write!(f, "That lobby doesn't exist."),
+            ApiError::LobbyNameTaken => write!(f, "There's already a lobby with that name."),
+            ApiError::LobbyFull => write!(f, "That lobby is full."),
+            ApiError::LobbyClosed => write!(f, "That lobby's game is over."),
+            ApiError::WrongGameType => write!(f, "That action is for a different game."),
+            ApiError::InvalidTicTacToeAction(action) => match action {
+                InvalidAction::CantJoinTwice => write!(f, "You've already joined this game."),
+                InvalidAction::StillWaitingForPlayers => {
+                    write!(f, "The game is still waiting for players.")
+                }
+                InvalidAction::GameAlreadyInPlay => write!(f, "The game has already started."),
+                InvalidAction::PositionOutOfBounds => write!(f, "That position is off the board."),
+                InvalidAction::AlreadyPlacedThere => write!(f, "There's already a token there."),
+                InvalidAction::NotYourTurn => write!(f, "It's not your turn."),
+            },
+            ApiError::InvalidRockPaperScissorsAction(action) => match action {
+                rockpaperscissors::InvalidAction::UnknownPlayer => {
+                    write!(f, "You're not playing in this game.")
+                }
+                rockpaperscissors::InvalidAction::AlreadyMoved => {
+                    write!(f, "You've already chosen a move this round.")
+                }
+                rockpaperscissors::InvalidAction::WrongPhase => {
+                    write!(f, "You can't do that right now.")
+                }
+                rockpaperscissors::InvalidAction::GameFull => write!(f, "The game is full."),
+                rockpaperscissors::InvalidAction::AlreadyJoined => {
+                    write!(f, "You've already joined this game.")
+                }
+            },
+            ApiError::Busy => write!(f, "The server is busy, try again in a moment."),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// The body of every failed request.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ErrorResponse {
+    pub error: ApiError,
+}
+
+#[test]
+fn test_error_codes() {
+    let json = |error: ApiError| serde_json::to_value(ErrorResponse { error }).unwrap();
+
+    assert_eq!(
+        json(ApiError::LobbyNotFound),
+        serde_json::json!({ "error": { "code": "lobby_not_found" } })
+    );
+    assert_eq!(
+        json(ApiError::LobbyFull),
+        serde_json::json!({ "error": { "code": "lobby_full" } })
+    );
+    assert_eq!(ApiError::LobbyClosed.status(), 409);
+    assert_eq!(ApiError::InvalidBody.status(), 422);
+    assert_eq!(ApiError::Busy.status(), 503);
+    assert_eq!(
+        json(ApiError::InvalidTicTacToeAction(
+            tictactoe::InvalidAction::NotYourTurn
+        )),
+        serde_json::json!({
+            "error": { "code": "invalid_tic_tac_toe_action", "details": "not_your_turn" }
+        })
+    );
+}
+
+#[test]
+fn test_error_round_trip() {
+    let response = ErrorResponse {
+        error: ApiError::BadRequest(String::from("invalid player id")),
+    };
+
+    let text = serde_json::to_string(&response).unwrap();
+
+    assert_eq!(
+        serde_json::from_str::<ErrorResponse>(&text).unwrap(),
+        response
+    );
+    assert_eq!(response.error.status(), 400);
+}
@@ -1,20 +1,58 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::time::Duration;
 use uuid::Uuid;
 
+pub mod error;
 pub mod rockpaperscissors;
+pub mod rockpaperscissors_strategy;
 pub mod tictactoe;
-#[derive(Serialize, Deserialize, Clone, Debug)]
+pub mod tictactoe_solver;
+
+use error::ApiError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(tag = "game", rename_all = "snake_case")]
 pub enum Action {
     TicTacToe(tictactoe::PlayerAction),
     RockPaperScissors(rockpaperscissors::PlayerAction),
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(tag = "game", rename_all = "snake_case")]
 pub enum Game {
     TicTacToe(tictactoe::GameState),
     RockPaperScissors(rockpaperscissors::GameState),
 }
 
+impl Action {
+    pub fn is_join(&self) -> bool {
+        match self {
+            Action::TicTacToe(tictactoe::PlayerAction::Join { .. })
+            | Action::RockPaperScissors(rockpaperscissors::PlayerAction::Join { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Game {
+    /// Every action `player` can currently take in this game.
+    pub fn legal_actions(&self, player: Uuid) -> Vec<Action> {
+        match self {
+            Game::TicTacToe(state) => tictactoe::legal_actions(state, player)
+                .into_iter()
+                .map(Action::TicTacToe)
+                .collect(),
+            Game::RockPaperScissors(state) => state
+                .legal_actions(player)
+                .into_iter()
+                .map(Action::RockPaperScissors)
+                .collect(),
+        }
+    }
+}
+
 impl From<GameType> for Game {
     fn from(_type: GameType) -> Game {
         match _type {
@@ -26,28 +64,521 @@ impl From<GameType> for Game {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum GameType {
     TicTacToe,
     RockPaperScissors,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct Lobby {
+    /// Made up by the server, and what the lobby goes by in paths.
+    pub id: String,
+
+    /// Shown to players. No two lobbies that are open or in progress share a name.
     pub name: String,
     pub players: usize,
     pub max_players: usize,
     pub game_type: GameType,
     pub game: Game,
+
+    /// Bumped every time `game` changes.
+    #[serde(default)]
+    pub version: u64,
+
+    #[serde(default)]
+    pub status: LobbyStatus,
+}
+
+/// Where the game in a lobby is at.
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LobbyStatus {
+    /// Waiting for players to join.
+    Open,
+    InProgress,
+    Finished,
+
+    /// Nobody has played for a while, so it's unlikely to finish.
+    Abandoned,
+}
+
+impl Default for LobbyStatus {
+    fn default() -> Self {
+        LobbyStatus::Open
+    }
+}
+
+impl Lobby {
+    /// The order `/lobbies` lists lobbies in: by name ignoring case, then by ID so lobbies with
+    /// the same name keep their places.
+    pub fn by_name(&self, other: &Lobby) -> Ordering {
+        self.name
+            .to_lowercase()
+            .cmp(&other.name.to_lowercase())
+            .then_with(|| self.id.cmp(&other.id))
+    }
+
+    /// Whether the lobby has `name` to itself, ignoring case. It gives it up once it closes.
+    pub fn holds_name(&self, name: &str) -> bool {
+        !self.is_closed() && self.name.to_lowercase() == name.to_lowercase()
+    }
+
+    /// Whether the game is over one way or another, so nothing more can happen in the lobby.
+    pub fn is_closed(&self) -> bool {
+        self.status == LobbyStatus::Finished || self.status == LobbyStatus::Abandoned
+    }
+
+    /// Mark the lobby abandoned if it's been `idle` for at least `limit` without its game
+    /// finishing. There's no coming back from that.
+    pub fn abandon_if_idle(&mut self, idle: Duration, limit: Duration) {
+        if idle >= limit && !self.is_closed() {
+            self.status = LobbyStatus::Abandoned;
+        }
+    }
+
+    /// Whether the lobby lets `action` through to its game, before the game's own rules get a
+    /// say. Nothing gets through once it's closed, and joins only while there's a free seat.
+    pub fn admit(&self, action: &Action) -> Result<(), ApiError> {
+        if self.is_closed() {
+            Err(ApiError::LobbyClosed)
+        } else if action.is_join() && self.players >= self.max_players {
+            Err(ApiError::LobbyFull)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Catch up with a change to `game`, `joined` being whether a player took a seat.
+    pub fn changed(&mut self, joined: bool) {
+        self.version += 1;
+
+        if joined {
+            self.players += 1;
+        }
+
+        self.status = self.game.status();
+    }
+}
+
+impl Game {
+    /// The status of a lobby with this game in it, short of it being abandoned.
+    pub fn status(&self) -> LobbyStatus {
+        match self {
+            Game::TicTacToe(tictactoe::GameState::WaitingForPlayers { .. })
+            | Game::RockPaperScissors(rockpaperscissors::GameState::WaitingForPlayers { .. }) => {
+                LobbyStatus::Open
+            }
+            Game::TicTacToe(tictactoe::GameState::WaitingForInput { .. })
+            | Game::RockPaperScissors(rockpaperscissors::GameState::WaitingForInput { .. }) => {
+                LobbyStatus::InProgress
+            }
+            Game::TicTacToe(tictactoe::GameState::GameOver { .. })
+            | Game::RockPaperScissors(rockpaperscissors::GameState::GameOver { .. }) => {
+                LobbyStatus::Finished
+            }
+        }
+    }
+}
+
+/// Narrows down the lobbies listed by `/lobbies`, as its query parameters. Every part is optional,
+/// so the default lists all of them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct LobbyQuery {
+    pub game_type: Option<GameType>,
+    pub status: Option<LobbyStatus>,
+
+    /// Only lobbies with names starting with this, ignoring case.
+    pub name: Option<String>,
+
+    /// Skip this many of the matching lobbies, in the order of [`Lobby::by_name`].
+    pub offset: Option<usize>,
+
+    /// List at most this many lobbies.
+    pub limit: Option<usize>,
+}
+
+impl LobbyQuery {
+    pub fn matches(&self, lobby: &Lobby) -> bool {
+        self.game_type
+            .as_ref()
+            .map_or(true, |game_type| *game_type == lobby.game_type)
+            && self.status.map_or(true, |status| status == lobby.status)
+            && self.name.as_ref().map_or(true, |prefix| {
+                lobby
+                    .name
+                    .to_lowercase()
+                    .starts_with(&prefix.to_lowercase())
+            })
+    }
+
+    /// The matching lobbies, sorted by [`Lobby::by_name`] and paged by `offset` and `limit`.
+    pub fn apply<'a>(&self, lobbies: impl IntoIterator<Item = &'a Lobby>) -> Vec<&'a Lobby> {
+        let mut matching: Vec<&Lobby> = lobbies
+            .into_iter()
+            .filter(|lobby| self.matches(lobby))
+            .collect();
+
+        matching.sort_by(|a, b| a.by_name(b));
+
+        matching
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+/// A game as of some version of its lobby, from `/lobbies/<lobby>/state?since=<version>`.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct VersionedGame {
+    pub version: u64,
+    pub game: Game,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct JoinResponse {
     pub player: Uuid,
     pub game_type: GameType,
 }
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct CreateLobbyRequest {
     pub name: String,
     pub game: GameType,
+
+    /// Fill the second seat with a server-side bot.
+    #[serde(default)]
+    pub bot: Option<BotDifficulty>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct CreateLobbyResponse {
+    pub id: String,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BotDifficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct AddBotRequest {
+    pub difficulty: BotDifficulty,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct AnalysisRequest {
+    pub board: tictactoe::Board,
+    pub to_move: tictactoe::BoardCell,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct RegisterBotRequest {
+    pub name: String,
+}
+
+/// Sent with the `X-Api-Key` header to authenticate as a bot.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct BotCredentials {
+    pub bot: Uuid,
+    pub api_key: String,
+}
+
+/// A lobby where it's a bot's turn to act.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct TurnEvent {
+    /// The lobby's ID.
+    pub lobby: String,
+    pub player: Uuid,
+    pub game: Game,
+    /// The lobby's version, which moves on whenever `game` changes.
+    pub version: u64,
+}
+
+/// Bump whenever these types change in a way that breaks clients or servers built against an older
+/// `common`, including adding a game type.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+/// Optional parts of the API this server offers, which clients can work without.
+pub const FEATURES: &[&str] = &["websocket", "long_poll", "bots", "analysis", "lobby_query"];
+
+/// What a server speaks, from `/version`. Clients check this before making any other request.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct VersionInfo {
+    pub protocol: u32,
+    pub game_types: Vec<GameType>,
+    pub features: Vec<String>,
+}
+
+impl VersionInfo {
+    /// Describes this build.
+    pub fn current() -> Self {
+        Self {
+            protocol: PROTOCOL_VERSION,
+            game_types: vec![GameType::TicTacToe, GameType::RockPaperScissors],
+            features: FEATURES
+                .iter()
+                .map(|&feature| String::from(feature))
+                .collect(),
+        }
+    }
+
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// Why this build can't talk to a server speaking `protocol`, if it can't.
+///
+/// Check this before decoding the rest of a [`VersionInfo`], which may list game types from a
+/// newer protocol.
+pub fn protocol_incompatibility(protocol: u32) -> Option<String> {
+    if protocol == PROTOCOL_VERSION {
+        return None;
+    }
+
+    let newer = if protocol > PROTOCOL_VERSION {
+        "a newer"
+    } else {
+        "an older"
+    };
+
+    Some(format!(
+        "The server speaks {} protocol (version {}, this client speaks {}). \
+         Use a client built for the same version as the server.",
+        newer, protocol, PROTOCOL_VERSION
+    ))
+}
+
+/// The port the server listens on for WebSocket connections, next to its HTTP API.
+pub const WEBSOCKET_PORT: u16 = 8001;
+
+/// Pushed to clients watching a lobby over `ws://<host>:WEBSOCKET_PORT/lobbies/<lobby>`.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// The game in the lobby changed. Also sent as soon as a client starts watching.
+    State(Game),
+
+    /// A player joined the lobby.
+    Lobby(Lobby),
+
+    Error(String),
+}
+
+#[cfg(test)]
+fn lobby(id: &str, name: &str, game_type: GameType) -> Lobby {
+    Lobby {
+        id: String::from(id),
+        name: String::from(name),
+        players: 0,
+        max_players: 2,
+        game: Game::from(game_type.clone()),
+        game_type,
+        version: 0,
+        status: LobbyStatus::Open,
+    }
+}
+
+#[cfg(test)]
+fn ids(lobbies: Vec<&Lobby>) -> Vec<&str> {
+    lobbies.iter().map(|lobby| lobby.id.as_str()).collect()
+}
+
+#[test]
+fn test_lobby_query_name_prefix() {
+    let query = LobbyQuery {
+        name: Some(String::from("fri")),
+        ..LobbyQuery::default()
+    };
+
+    assert!(query.matches(&lobby("1", "Friday night", GameType::TicTacToe)));
+    assert!(query.matches(&lobby("2", "FRIENDS ONLY", GameType::TicTacToe)));
+    assert!(!query.matches(&lobby("3", "Not friday", GameType::TicTacToe)));
+    assert!(!query.matches(&lobby("4", "Fr", GameType::TicTacToe)));
+}
+
+#[test]
+fn test_lobby_query_order_ignores_case() {
+    let lobbies = vec![
+        lobby("1", "banana", GameType::TicTacToe),
+        lobby("2", "Cherry", GameType::TicTacToe),
+        lobby("4", "apple", GameType::TicTacToe),
+        lobby("3", "Apple", GameType::TicTacToe),
+    ];
+
+    assert_eq!(
+        ids(LobbyQuery::default().apply(&lobbies)),
+        ["3", "4", "1", "2"]
+    );
+}
+
+#[test]
+fn test_lobby_query_paging() {
+    let lobbies = vec![
+        lobby("1", "a", GameType::TicTacToe),
+        lobby("2", "b", GameType::TicTacToe),
+        lobby("3", "c", GameType::TicTacToe),
+    ];
+
+    let page = |offset, limit| {
+        let query = LobbyQuery {
+            offset,
+            limit,
+            ..LobbyQuery::default()
+        };
+        ids(query.apply(&lobbies))
+    };
+
+    assert_eq!(page(Some(1), Some(1)), ["2"]);
+    assert_eq!(page(Some(2), None), ["3"]);
+    assert!(page(Some(3), None).is_empty());
+    assert!(page(Some(10), Some(5)).is_empty());
+    assert!(page(None, Some(0)).is_empty());
+}
+
+#[cfg(test)]
+fn join(player: Uuid) -> Action {
+    Action::TicTacToe(tictactoe::PlayerAction::Join { player })
+}
+
+#[cfg(test)]
+fn place(player: Uuid) -> Action {
+    Action::TicTacToe(tictactoe::PlayerAction::PlaceToken {
+        player,
+        position: (0, 0),
+    })
+}
+
+#[test]
+fn test_game_status() {
+    let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let open = tictactoe::GameState::default();
+    assert_eq!(Game::TicTacToe(open.clone()).status(), LobbyStatus::Open);
+
+    let playing = [p1, p2].iter().fold(open, |state, &player| {
+        tictactoe::process_input(tictactoe::PlayerAction::Join { player }, state).unwrap()
+    });
+    assert_eq!(Game::TicTacToe(playing).status(), LobbyStatus::InProgress);
+
+    let over = tictactoe::GameState::GameOver {
+        winner: None,
+        board: [[None; 3]; 3],
+    };
+    assert_eq!(Game::TicTacToe(over).status(), LobbyStatus::Finished);
+
+    let playing = rockpaperscissors::GameState::WaitingForInput {
+        players: vec![p1, p2],
+        round: 0,
+        input: None,
+        history: vec![],
+    };
+    assert_eq!(
+        Game::RockPaperScissors(playing).status(),
+        LobbyStatus::InProgress
+    );
+
+    let over = rockpaperscissors::GameState::GameOver {
+        winner: p1,
+        history: vec![],
+    };
+    assert_eq!(
+        Game::RockPaperScissors(over).status(),
+        LobbyStatus::Finished
+    );
+}
+
+#[test]
+fn test_full_lobby() {
+    let mut lobby = lobby("1", "Full", GameType::TicTacToe);
+    let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+
+    for &player in &[p1, p2] {
+        assert_eq!(lobby.admit(&join(player)), Ok(()));
+        lobby.game = match lobby.game {
+            Game::TicTacToe(state) => Game::TicTacToe(
+                tictactoe::process_input(tictactoe::PlayerAction::Join { player }, state).unwrap(),
+            ),
+            game => game,
+        };
+        lobby.changed(true);
+    }
+
+    assert_eq!(lobby.players, 2);
+    assert_eq!(lobby.version, 2);
+    assert_eq!(lobby.status, LobbyStatus::InProgress);
+    assert_eq!(lobby.admit(&join(Uuid::new_v4())), Err(ApiError::LobbyFull));
+    assert_eq!(lobby.admit(&place(p1)), Ok(()));
+}
+
+#[test]
+fn test_finished_lobby() {
+    let mut lobby = lobby("1", "Done", GameType::TicTacToe);
+    lobby.game = Game::TicTacToe(tictactoe::GameState::GameOver {
+        winner: None,
+        board: [[None; 3]; 3],
+    });
+    lobby.changed(false);
+
+    assert_eq!(lobby.status, LobbyStatus::Finished);
+    assert_eq!(
+        lobby.admit(&join(Uuid::new_v4())),
+        Err(ApiError::LobbyClosed)
+    );
+    assert_eq!(
+        lobby.admit(&place(Uuid::new_v4())),
+        Err(ApiError::LobbyClosed)
+    );
+
+    // Finished is as far as it goes.
+    lobby.abandon_if_idle(Duration::from_secs(3600), Duration::from_secs(600));
+    assert_eq!(lobby.status, LobbyStatus::Finished);
+}
+
+#[test]
+fn test_abandoned_lobby() {
+    let limit = Duration::from_secs(600);
+    let mut lobby = lobby("1", "Quiet", GameType::TicTacToe);
+
+    lobby.abandon_if_idle(Duration::from_secs(599), limit);
+    assert_eq!(lobby.status, LobbyStatus::Open);
+
+    lobby.abandon_if_idle(limit, limit);
+    assert_eq!(lobby.status, LobbyStatus::Abandoned);
+    assert!(lobby.is_closed());
+
+    // Coming back doesn't reopen it.
+    assert_eq!(
+        lobby.admit(&join(Uuid::new_v4())),
+        Err(ApiError::LobbyClosed)
+    );
+    assert_eq!(
+        lobby.admit(&place(Uuid::new_v4())),
+        Err(ApiError::LobbyClosed)
+    );
+}
+
+#[test]
+fn test_lobby_names() {
+    let mut lobby = lobby("1", "Friday Night", GameType::TicTacToe);
+
+    assert!(lobby.holds_name("Friday Night"));
+    assert!(lobby.holds_name("FRIDAY night"));
+    assert!(!lobby.holds_name("Friday"));
+
+    lobby.status = LobbyStatus::InProgress;
+    assert!(lobby.holds_name("friday night"));
+
+    lobby.status = LobbyStatus::Finished;
+    assert!(!lobby.holds_name("friday night"));
+
+    lobby.status = LobbyStatus::Abandoned;
+    assert!(!lobby.holds_name("friday night"));
 }
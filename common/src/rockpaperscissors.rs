@@ -1,29 +1,53 @@
-use bimap::BiMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 type PlayerID = Uuid;
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum Move {
     Rock,
     Paper,
     Scissors,
 }
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, JsonSchema)]
+#[schemars(rename = "RockPaperScissorsPlayerAction")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum PlayerAction {
     Join { player: PlayerID },
     Move { player: PlayerID, action: Move },
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// An input action would result in an invalid or inconsistent game state.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[schemars(rename = "RockPaperScissorsInvalidAction")]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidAction {
+    /// The player isn't in this game.
+    UnknownPlayer,
+
+    /// The player already chose a move this round.
+    AlreadyMoved,
+
+    /// The action doesn't fit the current phase of the game, e.g. moving before it starts.
+    WrongPhase,
+
+    GameFull,
+    AlreadyJoined,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 pub struct HistoryEntry {
-    pub moves: BiMap<PlayerID, Move>,
+    pub moves: HashMap<PlayerID, Move>,
     pub winner: Option<PlayerID>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[schemars(rename = "RockPaperScissorsGameState")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum GameState {
     WaitingForPlayers {
         /// Players
@@ -106,10 +130,44 @@ impl GameState {
         }
     }
 
-    pub fn apply(&self, action: PlayerAction) -> Result<GameState, String> {
+    /// Every action `player` can currently take.
+    pub fn legal_actions(&self, player: PlayerID) -> Vec<PlayerAction> {
+        match self {
+            GameState::WaitingForPlayers { players } => {
+                if players.contains(&player) {
+                    vec![]
+                } else {
+                    vec![PlayerAction::Join { player }]
+                }
+            }
+            GameState::WaitingForInput { players, input, .. } => {
+                if !players.contains(&player) {
+                    return vec![];
+                }
+
+                match input {
+                    Some((p, _)) if *p == player => vec![],
+                    _ => [Move::Rock, Move::Paper, Move::Scissors]
+                        .iter()
+                        .map(|action| PlayerAction::Move {
+                            player,
+                            action: *action,
+                        })
+                        .collect(),
+                }
+            }
+            GameState::GameOver { .. } => vec![],
+        }
+    }
+
+    pub fn apply(&self, action: PlayerAction) -> Result<GameState, InvalidAction> {
         match self {
             GameState::WaitingForPlayers { players } => match action {
                 PlayerAction::Join { player: new_player } => {
+                    if players.contains(&new_player) {
+                        return Err(InvalidAction::AlreadyJoined);
+                    }
+
                     if players.len() == 0 {
                         Ok(GameState::WaitingForPlayers {
                             players: vec![new_player],
@@ -126,10 +184,7 @@ impl GameState {
                         })
                     }
                 }
-                _ => Err(format!(
-                    "invalid action {:?} for given state {:?}",
-                    self, action
-                )),
+                PlayerAction::Move { .. } => Err(InvalidAction::WrongPhase),
             },
             GameState::WaitingForInput {
                 players,
@@ -139,7 +194,7 @@ impl GameState {
             } => match action {
                 PlayerAction::Move { player, action } => {
                     if !players.contains(&player) {
-                        return Err(format!("Invalid player"));
+                        return Err(InvalidAction::UnknownPlayer);
                     }
 
                     match input {
@@ -154,12 +209,12 @@ impl GameState {
                             let p2move = action;
 
                             if *p1 == p2 {
-                                return Ok(self.clone()); // #TODO Error handling here
+                                return Err(InvalidAction::AlreadyMoved);
                             }
 
                             let round_winner = rock_paper_scissors(p1, p1move, &p2, &p2move);
 
-                            let mut moves: BiMap<Uuid, Move> = BiMap::new();
+                            let mut moves: HashMap<Uuid, Move> = HashMap::new();
                             moves.insert(*p1, *p1move);
                             moves.insert(p2, p2move);
 
@@ -188,12 +243,15 @@ impl GameState {
                         }
                     }
                 }
-                _ => Err(format!(
-                    "invalid action {:?} for given state {:?}",
-                    self, action
-                )),
+                PlayerAction::Join { player } => {
+                    if players.contains(&player) {
+                        Err(InvalidAction::AlreadyJoined)
+                    } else {
+                        Err(InvalidAction::GameFull)
+                    }
+                }
             },
-            GameState::GameOver { .. } => Ok(self.clone()),
+            GameState::GameOver { .. } => Err(InvalidAction::WrongPhase),
         }
     }
 }
@@ -248,3 +306,166 @@ fn test_history() {
         _ => assert!(false, "game should be over"),
     }
 }
+
+#[test]
+fn test_legal_actions() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+    let mut state = GameState::default();
+
+    assert_eq!(state.legal_actions(p1).len(), 1);
+
+    state = state.apply(PlayerAction::Join { player: p1 }).unwrap();
+    assert!(state.legal_actions(p1).is_empty());
+
+    state = state.apply(PlayerAction::Join { player: p2 }).unwrap();
+    assert_eq!(state.legal_actions(p1).len(), 3);
+    assert!(state.legal_actions(Uuid::new_v4()).is_empty());
+
+    state = state
+        .apply(PlayerAction::Move {
+            player: p1,
+            action: Move::Rock,
+        })
+        .unwrap();
+    assert!(state.legal_actions(p1).is_empty());
+    assert_eq!(state.legal_actions(p2).len(), 3);
+}
+
+#[test]
+fn test_tied_round_keeps_both_moves() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+    let mut state = GameState::default();
+
+    state = state.apply(PlayerAction::Join { player: p1 }).unwrap();
+    state = state.apply(PlayerAction::Join { player: p2 }).unwrap();
+
+    for player in &[p1, p2] {
+        state = state
+            .apply(PlayerAction::Move {
+                player: *player,
+                action: Move::Rock,
+            })
+            .unwrap();
+    }
+
+    match state {
+        GameState::WaitingForInput { history, .. } => {
+            assert_eq!(history[0].moves.len(), 2);
+            assert_eq!(history[0].winner, None);
+        }
+        _ => assert!(false, "game should still be going"),
+    }
+}
+
+#[test]
+fn test_double_join() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+    let mut state = GameState::default();
+
+    state = state.apply(PlayerAction::Join { player: p1 }).unwrap();
+
+    assert_eq!(
+        state.apply(PlayerAction::Join { player: p1 }).unwrap_err(),
+        InvalidAction::AlreadyJoined
+    );
+
+    state = state.apply(PlayerAction::Join { player: p2 }).unwrap();
+
+    assert_eq!(
+        state.apply(PlayerAction::Join { player: p2 }).unwrap_err(),
+        InvalidAction::AlreadyJoined
+    );
+    assert_eq!(
+        state
+            .apply(PlayerAction::Join {
+                player: Uuid::new_v4()
+            })
+            .unwrap_err(),
+        InvalidAction::GameFull
+    );
+}
+
+#[test]
+fn test_double_move() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+    let mut state = GameState::default();
+
+    state = state.apply(PlayerAction::Join { player: p1 }).unwrap();
+    state = state.apply(PlayerAction::Join { player: p2 }).unwrap();
+
+    state = state
+        .apply(PlayerAction::Move {
+            player: p1,
+            action: Move::Rock,
+        })
+        .unwrap();
+
+    assert_eq!(
+        state
+            .apply(PlayerAction::Move {
+                player: p1,
+                action: Move::Paper,
+            })
+            .unwrap_err(),
+        InvalidAction::AlreadyMoved
+    );
+}
+
+#[test]
+fn test_invalid_moves() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+    let mut state = GameState::default();
+
+    state = state.apply(PlayerAction::Join { player: p1 }).unwrap();
+
+    assert_eq!(
+        state
+            .apply(PlayerAction::Move {
+                player: p1,
+                action: Move::Rock,
+            })
+            .unwrap_err(),
+        InvalidAction::WrongPhase
+    );
+
+    state = state.apply(PlayerAction::Join { player: p2 }).unwrap();
+
+    assert_eq!(
+        state
+            .apply(PlayerAction::Move {
+                player: Uuid::new_v4(),
+                action: Move::Rock,
+            })
+            .unwrap_err(),
+        InvalidAction::UnknownPlayer
+    );
+
+    while let GameState::WaitingForInput { .. } = state {
+        state = state
+            .apply(PlayerAction::Move {
+                player: p1,
+                action: Move::Rock,
+            })
+            .unwrap()
+            .apply(PlayerAction::Move {
+                player: p2,
+                action: Move::Paper,
+            })
+            .unwrap();
+    }
+
+    assert_eq!(
+        state
+            .apply(PlayerAction::Move {
+                player: p1,
+                action: Move::Rock,
+            })
+            .unwrap_err(),
+        InvalidAction::WrongPhase
+    );
+}
@@ -0,0 +1,372 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::rockpaperscissors::{HistoryEntry, Move};
+use uuid::Uuid;
+
+const MOVES: [Move; 3] = [Move::Rock, Move::Paper, Move::Scissors];
+
+/// How many of the most recent rounds a `Strategy` remembers, so long matches don't make every
+/// prediction slower than the last.
+const MEMORY: usize = 200;
+
+/// One finished round, seen from our side.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Round {
+    pub ours: Move,
+    pub theirs: Move,
+}
+
+/// Turn a game's history into rounds from the point of view of `player`.
+pub fn rounds(history: &[HistoryEntry], player: Uuid) -> Vec<Round> {
+    history
+        .iter()
+        .filter_map(|entry| {
+            let ours = entry.moves.get(&player)?;
+            let theirs = entry.moves.iter().find(|(p, _)| **p != player)?.1;
+
+            Some(Round {
+                ours: *ours,
+                theirs: *theirs,
+            })
+        })
+        .collect()
+}
+
+/// The move which beats `player_move`.
+pub fn beats(player_move: Move) -> Move {
+    match player_move {
+        Move::Rock => Move::Paper,
+        Move::Paper => Move::Scissors,
+        Move::Scissors => Move::Rock,
+    }
+}
+
+/// +1 if `ours` beats `theirs`, -1 if it loses and 0 for a tie.
+fn payoff(ours: Move, theirs: Move) -> f64 {
+    if ours == theirs {
+        0.0
+    } else if beats(theirs) == ours {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+fn most_frequent<I: Iterator<Item = Move>>(moves: I) -> Option<Move> {
+    let mut counts = [0usize; 3];
+
+    for m in moves {
+        counts[m as usize] += 1;
+    }
+
+    MOVES
+        .iter()
+        .zip(counts.iter())
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(_, count)| **count)
+        .map(|(m, _)| *m)
+}
+
+/// Guesses the opponent's next move from the rounds played so far, oldest first.
+pub trait Predictor: Send {
+    fn predict(&self, rounds: &[Round]) -> Option<Move>;
+}
+
+/// Predicts the opponent's most common move, optionally only over their most recent moves.
+pub struct FrequencyPredictor {
+    window: Option<usize>,
+}
+
+impl FrequencyPredictor {
+    pub fn new() -> Self {
+        Self { window: None }
+    }
+
+    pub fn windowed(window: usize) -> Self {
+        Self {
+            window: Some(window),
+        }
+    }
+}
+
+impl Default for FrequencyPredictor {
+    fn default() -> Self {
+        FrequencyPredictor::new()
+    }
+}
+
+impl Predictor for FrequencyPredictor {
+    fn predict(&self, rounds: &[Round]) -> Option<Move> {
+        let start = match self.window {
+            Some(window) if rounds.len() > window => rounds.len() - window,
+            _ => 0,
+        };
+
+        most_frequent(rounds.iter().skip(start).map(|round| round.theirs))
+    }
+}
+
+/// Predicts the opponent's next move from what followed the same recent moves in the past.
+///
+/// The context is the opponent's last `order` moves, or with `joint` both players' moves, which
+/// also catches opponents that react to what we played.
+pub struct MarkovPredictor {
+    order: usize,
+    joint: bool,
+}
+
+impl MarkovPredictor {
+    pub fn new(order: usize) -> Self {
+        Self {
+            order,
+            joint: false,
+        }
+    }
+
+    pub fn joint(order: usize) -> Self {
+        Self { order, joint: true }
+    }
+
+    fn context(&self, rounds: &[Round]) -> Vec<(Option<Move>, Move)> {
+        rounds
+            .iter()
+            .map(|round| {
+                if self.joint {
+                    (Some(round.ours), round.theirs)
+                } else {
+                    (None, round.theirs)
+                }
+            })
+            .collect()
+    }
+}
+
+impl Predictor for MarkovPredictor {
+    fn predict(&self, rounds: &[Round]) -> Option<Move> {
+        if self.order == 0 || rounds.len() <= self.order {
+            return None;
+        }
+
+        let context = self.context(rounds);
+        let current = &context[context.len() - self.order..];
+
+        let followers = (self.order..context.len())
+            .filter(|i| &context[i - self.order..*i] == current)
+            .map(|i| context[i].1);
+
+        most_frequent(followers)
+    }
+}
+
+/// Plays against several predictors at once, following whichever one would have done best in
+/// the recent rounds. Falls back to random moves while no predictor is doing better than chance.
+pub struct Strategy {
+    predictors: Vec<Box<dyn Predictor>>,
+    scores: Vec<f64>,
+    rounds: Vec<Round>,
+    decay: f64,
+}
+
+impl Strategy {
+    pub fn new(predictors: Vec<Box<dyn Predictor>>) -> Self {
+        Self {
+            scores: vec![0.0; predictors.len()],
+            predictors,
+            rounds: vec![],
+            decay: 0.9,
+        }
+    }
+
+    /// Frequency counters and Markov chains of a few orders.
+    pub fn ensemble() -> Self {
+        Strategy::new(vec![
+            Box::new(FrequencyPredictor::new()),
+            Box::new(FrequencyPredictor::windowed(5)),
+            Box::new(MarkovPredictor::new(1)),
+            Box::new(MarkovPredictor::new(2)),
+            Box::new(MarkovPredictor::joint(1)),
+            Box::new(MarkovPredictor::joint(2)),
+        ])
+    }
+
+    /// A strategy which has already seen `player`'s side of `history`.
+    pub fn from_history(mut self, history: &[HistoryEntry], player: Uuid) -> Self {
+        for round in rounds(history, player) {
+            self.observe(round);
+        }
+
+        self
+    }
+
+    /// Record a finished round, scoring each predictor on how its counter-move would have done.
+    pub fn observe(&mut self, round: Round) {
+        for (predictor, score) in self.predictors.iter().zip(self.scores.iter_mut()) {
+            *score *= self.decay;
+
+            if let Some(predicted) = predictor.predict(&self.rounds) {
+                *score += payoff(beats(predicted), round.theirs);
+            }
+        }
+
+        self.rounds.push(round);
+
+        if self.rounds.len() > MEMORY {
+            self.rounds.remove(0);
+        }
+    }
+
+    /// The opponent's most likely next move, according to the best scoring predictor.
+    pub fn predict(&self) -> Option<Move> {
+        self.predictors
+            .iter()
+            .zip(self.scores.iter())
+            .filter(|(_, score)| **score > 0.0)
+            .filter_map(|(predictor, score)| Some((predictor.predict(&self.rounds)?, *score)))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(m, _)| m)
+    }
+
+    pub fn choose<R: Rng>(&self, rng: &mut R) -> Move {
+        match self.predict() {
+            Some(predicted) => beats(predicted),
+            None => *MOVES.choose(rng).unwrap(),
+        }
+    }
+}
+
+/// Suggest a move for `player` given the game so far.
+pub fn suggest(history: &[HistoryEntry], player: Uuid) -> Move {
+    Strategy::ensemble()
+        .from_history(history, player)
+        .choose(&mut rand::thread_rng())
+}
+
+#[cfg(test)]
+fn play_against<F: FnMut(usize, &[Round]) -> Move>(
+    mut opponent: F,
+    rounds: usize,
+) -> (usize, usize) {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(457);
+    let mut strategy = Strategy::ensemble();
+    let mut played = vec![];
+
+    let mut wins = 0;
+    let mut losses = 0;
+
+    for i in 0..rounds {
+        let ours = strategy.choose(&mut rng);
+        let theirs = opponent(i, &played);
+
+        match payoff(ours, theirs) as i32 {
+            1 => wins += 1,
+            -1 => losses += 1,
+            _ => {}
+        }
+
+        let round = Round { ours, theirs };
+        strategy.observe(round);
+        played.push(round);
+    }
+
+    (wins, losses)
+}
+
+#[test]
+fn test_beats_constant_player() {
+    let (wins, losses) = play_against(|_, _| Move::Rock, 200);
+
+    assert!(wins > 180, "{} wins", wins);
+    assert!(losses < 10, "{} losses", losses);
+}
+
+#[test]
+fn test_beats_cycling_player() {
+    let (wins, losses) = play_against(|i, _| MOVES[i % 3], 200);
+
+    assert!(wins > 170, "{} wins", wins);
+    assert!(losses < 15, "{} losses", losses);
+}
+
+#[test]
+fn test_beats_biased_random_player() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let (wins, losses) = play_against(
+        |_, _| {
+            let roll: f64 = rng.gen();
+
+            if roll < 0.6 {
+                Move::Rock
+            } else if roll < 0.9 {
+                Move::Paper
+            } else {
+                Move::Scissors
+            }
+        },
+        1000,
+    );
+
+    assert!(wins > losses + 150, "{} wins, {} losses", wins, losses);
+}
+
+#[test]
+fn test_beats_reactive_player() {
+    // Always plays whatever would have beaten our previous move.
+    let (wins, losses) = play_against(
+        |_, played| match played.last() {
+            Some(round) => beats(round.ours),
+            None => Move::Rock,
+        },
+        200,
+    );
+
+    assert!(wins > 170, "{} wins", wins);
+    assert!(losses < 15, "{} losses", losses);
+}
+
+#[test]
+fn test_forgets_old_rounds() {
+    let mut strategy = Strategy::new(vec![Box::new(FrequencyPredictor::new())]);
+
+    for &theirs in [Move::Rock; 300].iter().chain([Move::Paper; MEMORY].iter()) {
+        strategy.observe(Round {
+            ours: Move::Scissors,
+            theirs,
+        });
+    }
+
+    assert_eq!(strategy.rounds.len(), MEMORY);
+    assert_eq!(strategy.predict(), Some(Move::Paper));
+}
+
+#[test]
+fn test_rounds_from_history() {
+    use std::collections::HashMap;
+
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+
+    let mut moves = HashMap::new();
+    moves.insert(p1, Move::Rock);
+    moves.insert(p2, Move::Paper);
+
+    let history = vec![HistoryEntry {
+        moves,
+        winner: Some(p2),
+    }];
+
+    assert_eq!(
+        rounds(&history, p1),
+        vec![Round {
+            ours: Move::Rock,
+            theirs: Move::Paper,
+        }]
+    );
+}
@@ -1,8 +1,10 @@
 use bimap::BiMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Hash, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum BoardCell {
     Circle,
     X,
@@ -10,7 +12,9 @@ pub enum BoardCell {
 
 pub type Board = [[Option<BoardCell>; 3]; 3];
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[schemars(rename = "TicTacToeGameState")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum GameState {
     WaitingForPlayers {
         players: Vec<Uuid>,
@@ -22,7 +26,9 @@ pub enum GameState {
         // The waiting player.
         waiting: Uuid,
 
-        // Which player has which token
+        // Which player has which token, serialized as a map from player to token
+        #[serde(with = "tokens")]
+        #[schemars(with = "std::collections::HashMap<Uuid, BoardCell>")]
         tokens: BiMap<Uuid, BoardCell>,
 
         // Board
@@ -34,13 +40,51 @@ pub enum GameState {
     },
 }
 
+/// Serializes the players' tokens as a plain map from player to token, rather than however the
+/// `bimap` crate happens to.
+mod tokens {
+    use std::collections::HashMap;
+
+    use bimap::BiMap;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use uuid::Uuid;
+
+    use super::BoardCell;
+
+    pub fn serialize<S: Serializer>(
+        tokens: &BiMap<Uuid, BoardCell>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(tokens.iter())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BiMap<Uuid, BoardCell>, D::Error> {
+        let mut tokens = BiMap::new();
+
+        for (player, token) in HashMap::<Uuid, BoardCell>::deserialize(deserializer)? {
+            if tokens.contains_right(&token) {
+                return Err(D::Error::custom("two players have the same token"));
+            }
+
+            tokens.insert(player, token);
+        }
+
+        Ok(tokens)
+    }
+}
+
 impl Default for GameState {
     fn default() -> Self {
         GameState::WaitingForPlayers { players: vec![] }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[schemars(rename = "TicTacToePlayerAction")]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum PlayerAction {
     Join {
         player: Uuid,
@@ -52,7 +96,9 @@ pub enum PlayerAction {
 }
 
 /// An input action would result in an invalid or inconsistent game state.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[schemars(rename = "TicTacToeInvalidAction")]
+#[serde(rename_all = "snake_case")]
 pub enum InvalidAction {
     CantJoinTwice,
     StillWaitingForPlayers,
@@ -81,13 +127,13 @@ fn check_rows(
     token: BoardCell,
     tokens: &BiMap<Uuid, BoardCell>,
 ) -> Option<Option<Option<Uuid>>> {
-    for col in 0..2 {
+    for col in 0..3 {
         let mut all_match = true;
 
-        for row in 0..2 {
+        for row in 0..3 {
             let ref cell = board[row][col];
 
-            if cell.is_none() || cell.unwrap() == token {
+            if *cell != Some(token) {
                 all_match = false;
                 break;
             }
@@ -106,13 +152,13 @@ fn check_columns(
     token: BoardCell,
     tokens: &BiMap<Uuid, BoardCell>,
 ) -> Option<Option<Option<Uuid>>> {
-    for row in 0..2 {
+    for row in 0..3 {
         let mut all_match = true;
 
-        for col in 0..2 {
+        for col in 0..3 {
             let ref cell = board[row][col];
 
-            if cell.is_none() || cell.unwrap() == token {
+            if *cell != Some(token) {
                 all_match = false;
                 break;
             }
@@ -141,7 +187,7 @@ fn check_diagonal(
     token: BoardCell,
     tokens: &BiMap<Uuid, BoardCell>,
 ) -> Option<Option<Option<Uuid>>> {
-    let left_down = [board[0][0], board[1][1], board[1][1]];
+    let left_down = [board[0][0], board[1][1], board[2][2]];
 
     if check_match(&left_down, &token) {
         return Some(Some(Some(*tokens.get_by_right(&token).unwrap())));
@@ -195,7 +241,6 @@ fn check_win_condition(board: Board, tokens: &BiMap<Uuid, BoardCell>) -> Option<
 pub fn process_input(input: PlayerAction, state: GameState) -> Result<GameState, InvalidAction> {
     match state {
         GameState::WaitingForPlayers { ref players } => {
-            println!("can't move yet, waiting for player 2");
             match input {
                 PlayerAction::Join { player } => {
                     let mut players = players.clone();
@@ -260,10 +305,10 @@ pub fn process_input(input: PlayerAction, state: GameState) -> Result<GameState,
                         return Err(InvalidAction::NotYourTurn);
                     }
 
-                    if position.0 > 3 {
+                    if position.0 >= 3 {
                         return Err(InvalidAction::PositionOutOfBounds);
                     }
-                    if position.1 > 3 {
+                    if position.1 >= 3 {
                         return Err(InvalidAction::PositionOutOfBounds);
                     }
 
@@ -296,6 +341,44 @@ pub fn process_input(input: PlayerAction, state: GameState) -> Result<GameState,
     }
 }
 
+/// Every action `player` can take in `state` that `process_input` would accept.
+pub fn legal_actions(state: &GameState, player: Uuid) -> Vec<PlayerAction> {
+    match state {
+        GameState::WaitingForPlayers { players } => {
+            if players.contains(&player) {
+                vec![]
+            } else {
+                vec![PlayerAction::Join { player }]
+            }
+        }
+        GameState::WaitingForInput {
+            active_player,
+            board,
+            ..
+        } => {
+            if *active_player != player {
+                return vec![];
+            }
+
+            let mut actions = vec![];
+
+            for (x, row) in board.iter().enumerate() {
+                for (y, cell) in row.iter().enumerate() {
+                    if cell.is_none() {
+                        actions.push(PlayerAction::PlaceToken {
+                            player,
+                            position: (x, y),
+                        });
+                    }
+                }
+            }
+
+            actions
+        }
+        GameState::GameOver { .. } => vec![],
+    }
+}
+
 /*#[test]
 fn test_gameplay() {
     let p1 = Uuid::new_v4();
@@ -467,3 +550,37 @@ fn test_win_condition_row() {
         _ => assert!(false),
     }
 }
+
+#[test]
+fn test_legal_actions() {
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+
+    let mut tokens = BiMap::new();
+    tokens.insert(p1, BoardCell::X);
+    tokens.insert(p2, BoardCell::Circle);
+
+    let s = GameState::WaitingForInput {
+        active_player: p1,
+        waiting: p2,
+        tokens,
+        board: [
+            [Some(BoardCell::X), None, None],
+            [None, Some(BoardCell::Circle), None],
+            [None, None, None],
+        ],
+    };
+
+    assert!(legal_actions(&s, p2).is_empty());
+
+    let actions = legal_actions(&s, p1);
+    assert_eq!(actions.len(), 7);
+
+    for action in actions {
+        assert!(process_input(action, s.clone()).is_ok());
+    }
+
+    let waiting = GameState::WaitingForPlayers { players: vec![p1] };
+    assert!(legal_actions(&waiting, p1).is_empty());
+    assert_eq!(legal_actions(&waiting, p2).len(), 1);
+}
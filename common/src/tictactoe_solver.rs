@@ -0,0 +1,414 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::tictactoe::{Board, BoardCell};
+
+const LINES: [[(usize, usize); 3]; 8] = [
+    [(0, 0), (0, 1), (0, 2)],
+    [(1, 0), (1, 1), (1, 2)],
+    [(2, 0), (2, 1), (2, 2)],
+    [(0, 0), (1, 0), (2, 0)],
+    [(0, 1), (1, 1), (2, 1)],
+    [(0, 2), (1, 2), (2, 2)],
+    [(0, 0), (1, 1), (2, 2)],
+    [(2, 0), (1, 1), (0, 2)],
+];
+
+/// The result of a position for the side to move, assuming both sides play perfectly.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+/// The game-theoretic value of a single move.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct MoveAnalysis {
+    pub position: (usize, usize),
+
+    /// The outcome for the side that made the move.
+    pub outcome: Outcome,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct Analysis {
+    /// The outcome for the side to move.
+    pub outcome: Outcome,
+
+    /// The moves that keep the best outcome, preferring the quickest win or the slowest loss.
+    /// Empty if the game is already over.
+    pub best_moves: Vec<(usize, usize)>,
+
+    /// Every legal move with its outcome.
+    pub moves: Vec<MoveAnalysis>,
+}
+
+/// Which token, if any, has three in a row.
+pub fn winner(board: &Board) -> Option<BoardCell> {
+    for line in LINES.iter() {
+        let [a, b, c] = *line;
+
+        if let Some(token) = board[a.0][a.1] {
+            if board[b.0][b.1] == Some(token) && board[c.0][c.1] == Some(token) {
+                return Some(token);
+            }
+        }
+    }
+
+    None
+}
+
+pub fn opponent(token: BoardCell) -> BoardCell {
+    match token {
+        BoardCell::X => BoardCell::Circle,
+        BoardCell::Circle => BoardCell::X,
+    }
+}
+
+/// The positions without a token in them.
+pub fn empty_cells(board: &Board) -> Vec<(usize, usize)> {
+    let mut cells = vec![];
+
+    for (x, row) in board.iter().enumerate() {
+        for (y, cell) in row.iter().enumerate() {
+            if cell.is_none() {
+                cells.push((x, y));
+            }
+        }
+    }
+
+    cells
+}
+
+/// Encode a board as a base 3 number so it can be used as a table key.
+fn encode(board: &Board) -> u32 {
+    let mut key = 0;
+
+    for row in board {
+        for cell in row {
+            key = key * 3
+                + match cell {
+                    None => 0,
+                    Some(BoardCell::X) => 1,
+                    Some(BoardCell::Circle) => 2,
+                };
+        }
+    }
+
+    key
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    score: i8,
+    bound: Bound,
+}
+
+fn outcome(score: i8) -> Outcome {
+    if score > 0 {
+        Outcome::Win
+    } else if score < 0 {
+        Outcome::Loss
+    } else {
+        Outcome::Draw
+    }
+}
+
+/// A negamax search with alpha-beta pruning over tic-tac-toe positions.
+///
+/// Scores are from the point of view of the side to move. A win scores higher the sooner it
+/// happens, so the best moves are also the fastest ones. Searched positions are kept in a
+/// transposition table, so reusing a `Solver` makes later queries cheap.
+pub struct Solver {
+    table: HashMap<(u32, BoardCell), Entry>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self {
+            table: HashMap::new(),
+        }
+    }
+
+    /// The outcome of `board` for `to_move`.
+    pub fn value(&mut self, board: &Board, to_move: BoardCell) -> Outcome {
+        let mut board = *board;
+        outcome(self.negamax(&mut board, to_move, -127, 127))
+    }
+
+    /// The outcome of `board` for `to_move` along with the value of every legal move.
+    pub fn analyse(&mut self, board: &Board, to_move: BoardCell) -> Analysis {
+        let mut board = *board;
+
+        if winner(&board).is_some() || empty_cells(&board).is_empty() {
+            return Analysis {
+                outcome: outcome(self.negamax(&mut board, to_move, -127, 127)),
+                best_moves: vec![],
+                moves: vec![],
+            };
+        }
+
+        let mut scores = vec![];
+
+        for (x, y) in empty_cells(&board) {
+            board[x][y] = Some(to_move);
+            let score = -self.negamax(&mut board, opponent(to_move), -127, 127);
+            board[x][y] = None;
+
+            scores.push(((x, y), score));
+        }
+
+        let best = scores.iter().map(|(_, score)| *score).max().unwrap();
+
+        Analysis {
+            outcome: outcome(best),
+            best_moves: scores
+                .iter()
+                .filter(|(_, score)| *score == best)
+                .map(|(position, _)| *position)
+                .collect(),
+            moves: scores
+                .iter()
+                .map(|(position, score)| MoveAnalysis {
+                    position: *position,
+                    outcome: outcome(*score),
+                })
+                .collect(),
+        }
+    }
+
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        to_move: BoardCell,
+        mut alpha: i8,
+        mut beta: i8,
+    ) -> i8 {
+        let empty = empty_cells(board);
+
+        if let Some(token) = winner(board) {
+            // The sooner the game was won, the more empty cells are left.
+            let score = 1 + empty.len() as i8;
+
+            return if token == to_move { score } else { -score };
+        }
+
+        if empty.is_empty() {
+            return 0;
+        }
+
+        let key = (encode(board), to_move);
+
+        if let Some(entry) = self.table.get(&key) {
+            match entry.bound {
+                Bound::Exact => return entry.score,
+                Bound::Lower => alpha = alpha.max(entry.score),
+                Bound::Upper => beta = beta.min(entry.score),
+            }
+
+            if alpha >= beta {
+                return entry.score;
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best = -127;
+
+        for (x, y) in empty {
+            board[x][y] = Some(to_move);
+            let score = -self.negamax(board, opponent(to_move), -beta, -alpha);
+            board[x][y] = None;
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.table.insert(key, Entry { score: best, bound });
+
+        best
+    }
+}
+
+#[cfg(test)]
+fn minimax(board: &mut Board, to_move: BoardCell) -> Outcome {
+    if let Some(token) = winner(board) {
+        return if token == to_move {
+            Outcome::Win
+        } else {
+            Outcome::Loss
+        };
+    }
+
+    let mut best = None;
+
+    for (x, y) in empty_cells(board) {
+        board[x][y] = Some(to_move);
+        let child = minimax(board, opponent(to_move));
+        board[x][y] = None;
+
+        let result = match child {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss => Outcome::Win,
+        };
+
+        best = match (best, result) {
+            (_, Outcome::Win) | (Some(Outcome::Win), _) => Some(Outcome::Win),
+            (_, Outcome::Draw) | (Some(Outcome::Draw), _) => Some(Outcome::Draw),
+            _ => Some(Outcome::Loss),
+        };
+    }
+
+    best.unwrap_or(Outcome::Draw)
+}
+
+#[test]
+fn test_empty_board_is_a_draw() {
+    let mut solver = Solver::new();
+    let analysis = solver.analyse(&[[None; 3]; 3], BoardCell::X);
+
+    assert_eq!(analysis.outcome, Outcome::Draw);
+    assert_eq!(analysis.moves.len(), 9);
+    assert_eq!(analysis.best_moves.len(), 9);
+}
+
+#[test]
+fn test_takes_the_immediate_win() {
+    let mut solver = Solver::new();
+    let board = [
+        [Some(BoardCell::X), Some(BoardCell::X), None],
+        [Some(BoardCell::Circle), Some(BoardCell::Circle), None],
+        [None, None, None],
+    ];
+
+    let analysis = solver.analyse(&board, BoardCell::X);
+    assert_eq!(analysis.outcome, Outcome::Win);
+    assert_eq!(analysis.best_moves, vec![(0, 2)]);
+
+    let analysis = solver.analyse(&board, BoardCell::Circle);
+    assert_eq!(analysis.outcome, Outcome::Win);
+    assert_eq!(analysis.best_moves, vec![(1, 2)]);
+}
+
+#[test]
+fn test_finished_game_has_no_moves() {
+    let mut solver = Solver::new();
+    let board = [
+        [Some(BoardCell::X), Some(BoardCell::X), Some(BoardCell::X)],
+        [Some(BoardCell::Circle), Some(BoardCell::Circle), None],
+        [None, None, None],
+    ];
+
+    let analysis = solver.analyse(&board, BoardCell::Circle);
+    assert_eq!(analysis.outcome, Outcome::Loss);
+    assert!(analysis.best_moves.is_empty());
+}
+
+/// Walk every position reachable from the empty board through the engine, checking that the
+/// engine ends the game exactly when the board shows a line or is full, and that the solver
+/// agrees with a plain minimax search.
+#[test]
+fn test_exhaustive_positions() {
+    use crate::tictactoe::{process_input, GameState, PlayerAction};
+    use bimap::BiMap;
+    use std::collections::HashSet;
+    use uuid::Uuid;
+
+    let p1 = Uuid::new_v4();
+    let p2 = Uuid::new_v4();
+
+    let mut tokens = BiMap::new();
+    tokens.insert(p1, BoardCell::X);
+    tokens.insert(p2, BoardCell::Circle);
+
+    let mut solver = Solver::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![GameState::WaitingForInput {
+        active_player: p1,
+        waiting: p2,
+        tokens: tokens.clone(),
+        board: [[None; 3]; 3],
+    }];
+
+    while let Some(state) = stack.pop() {
+        let (active_player, mut board) = match state {
+            GameState::WaitingForInput {
+                active_player,
+                board,
+                ..
+            } => (active_player, board),
+            _ => unreachable!(),
+        };
+
+        if !seen.insert(board) {
+            continue;
+        }
+
+        let to_move = *tokens.get_by_left(&active_player).unwrap();
+        assert_eq!(solver.value(&board, to_move), minimax(&mut board, to_move));
+
+        for (x, y) in empty_cells(&board) {
+            let action = PlayerAction::PlaceToken {
+                player: active_player,
+                position: (x, y),
+            };
+
+            let mut expected = board;
+            expected[x][y] = Some(to_move);
+
+            match process_input(action, state.clone()).unwrap() {
+                GameState::GameOver { winner: w, board } => {
+                    assert_eq!(board, expected);
+                    seen.insert(board);
+
+                    match w {
+                        Some(w) => {
+                            assert_eq!(Some(*tokens.get_by_left(&w).unwrap()), winner(&board))
+                        }
+                        None => {
+                            assert_eq!(winner(&board), None);
+                            assert!(empty_cells(&board).is_empty());
+                        }
+                    }
+                }
+                next @ GameState::WaitingForInput { .. } => {
+                    assert_eq!(winner(&expected), None);
+                    assert!(!empty_cells(&expected).is_empty());
+                    stack.push(next);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // The number of distinct positions reachable in a game of tic-tac-toe.
+    assert_eq!(seen.len(), 5478);
+}
@@ -0,0 +1,274 @@
+//! Pins down the JSON that goes over the wire, so it only changes on purpose.
+//!
+//! Sample values are compared against `tests/golden/*.json` and schemas against `schema/*.json`.
+//! After an intended change to the format, regenerate both with `UPDATE_GOLDEN=1 cargo test`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use bimap::BiMap;
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use common::error::{ApiError, ErrorResponse};
+use common::rockpaperscissors::{self, HistoryEntry, Move};
+use common::tictactoe::{self, BoardCell};
+use common::{
+    Action, CreateLobbyRequest, CreateLobbyResponse, Game, GameType, JoinResponse, Lobby,
+    LobbyQuery, LobbyStatus, ServerMessage, VersionInfo, VersionedGame, PROTOCOL_VERSION,
+};
+
+const P1: &str = "6f1c3c4e-2a4b-4a5e-9a42-2f6f0b0a1c01";
+const P2: &str = "0d9e8a7b-5c4d-4e3f-8a2b-1c0d9e8f7a02";
+
+fn players() -> (Uuid, Uuid) {
+    (Uuid::parse_str(P1).unwrap(), Uuid::parse_str(P2).unwrap())
+}
+
+fn updating() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some()
+}
+
+fn compare(path: PathBuf, actual: Value) {
+    if updating() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let text = serde_json::to_string_pretty(&actual).unwrap();
+        fs::write(&path, text + "\n").unwrap();
+        return;
+    }
+
+    let expected: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+
+    assert_eq!(
+        actual,
+        expected,
+        "{} changed, rerun with UPDATE_GOLDEN=1 if that's intended",
+        path.display()
+    );
+}
+
+/// Check `value` against its golden file, and that the golden file reads back to the same thing.
+fn golden<T: Serialize + DeserializeOwned>(name: &str, value: &T) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.json", name));
+
+    let json = serde_json::to_value(value).unwrap();
+    compare(path, json.clone());
+
+    let round_trip: T = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(serde_json::to_value(round_trip).unwrap(), json);
+}
+
+fn schema<T: JsonSchema>(name: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("schema")
+        .join(format!("{}.json", name));
+
+    compare(path, serde_json::to_value(schema_for!(T)).unwrap());
+}
+
+fn tictactoe_in_play() -> tictactoe::GameState {
+    let (p1, p2) = players();
+
+    let mut tokens = BiMap::new();
+    tokens.insert(p1, BoardCell::X);
+    tokens.insert(p2, BoardCell::Circle);
+
+    let mut board = [[None; 3]; 3];
+    board[0][0] = Some(BoardCell::X);
+    board[1][1] = Some(BoardCell::Circle);
+
+    tictactoe::GameState::WaitingForInput {
+        active_player: p1,
+        waiting: p2,
+        tokens,
+        board,
+    }
+}
+
+fn rockpaperscissors_in_play() -> rockpaperscissors::GameState {
+    let (p1, p2) = players();
+
+    let mut moves = HashMap::new();
+    moves.insert(p1, Move::Rock);
+    moves.insert(p2, Move::Scissors);
+
+    rockpaperscissors::GameState::WaitingForInput {
+        players: vec![p1, p2],
+        round: 1,
+        input: Some((p2, Move::Paper)),
+        history: vec![HistoryEntry {
+            moves,
+            winner: Some(p1),
+        }],
+    }
+}
+
+#[test]
+fn test_actions() {
+    let (p1, _) = players();
+
+    golden(
+        "action_tictactoe_join",
+        &Action::TicTacToe(tictactoe::PlayerAction::Join { player: p1 }),
+    );
+    golden(
+        "action_tictactoe_place_token",
+        &Action::TicTacToe(tictactoe::PlayerAction::PlaceToken {
+            player: p1,
+            position: (1, 2),
+        }),
+    );
+    golden(
+        "action_rockpaperscissors_move",
+        &Action::RockPaperScissors(rockpaperscissors::PlayerAction::Move {
+            player: p1,
+            action: Move::Scissors,
+        }),
+    );
+}
+
+#[test]
+fn test_games() {
+    let (p1, p2) = players();
+
+    golden(
+        "game_tictactoe_waiting_for_players",
+        &Game::TicTacToe(tictactoe::GameState::WaitingForPlayers { players: vec![p1] }),
+    );
+    golden(
+        "game_tictactoe_waiting_for_input",
+        &Game::TicTacToe(tictactoe_in_play()),
+    );
+    golden(
+        "game_tictactoe_game_over",
+        &Game::TicTacToe(tictactoe::GameState::GameOver {
+            winner: None,
+            board: [[Some(BoardCell::X); 3]; 3],
+        }),
+    );
+    golden(
+        "game_rockpaperscissors_waiting_for_input",
+        &Game::RockPaperScissors(rockpaperscissors_in_play()),
+    );
+    golden(
+        "game_rockpaperscissors_game_over",
+        &Game::RockPaperScissors(rockpaperscissors::GameState::GameOver {
+            winner: p2,
+            history: vec![],
+        }),
+    );
+}
+
+#[test]
+fn test_messages() {
+    let (p1, _) = players();
+
+    let lobby = Lobby {
+        id: String::from("9b2f6c1e-7d3a-4c5b-8e1f-0a2b3c4d5e06"),
+        name: String::from("lobby"),
+        players: 1,
+        max_players: 2,
+        game_type: GameType::TicTacToe,
+        game: Game::TicTacToe(tictactoe_in_play()),
+        version: 3,
+        status: LobbyStatus::InProgress,
+    };
+
+    golden("lobby", &lobby);
+    golden(
+        "versioned_game",
+        &VersionedGame {
+            version: 3,
+            game: lobby.game.clone(),
+        },
+    );
+    golden(
+        "join_response",
+        &JoinResponse {
+            player: p1,
+            game_type: GameType::RockPaperScissors,
+        },
+    );
+    golden(
+        "create_lobby_request",
+        &CreateLobbyRequest {
+            name: String::from("lobby"),
+            game: GameType::RockPaperScissors,
+            bot: None,
+        },
+    );
+    golden(
+        "create_lobby_response",
+        &CreateLobbyResponse {
+            id: lobby.id.clone(),
+        },
+    );
+    golden("server_message_lobby", &ServerMessage::Lobby(lobby));
+    golden(
+        "server_message_error",
+        &ServerMessage::Error(String::from("the lobby was replaced by a new game")),
+    );
+    golden("version_info", &VersionInfo::current());
+    golden(
+        "error_response",
+        &ErrorResponse {
+            error: ApiError::InvalidRockPaperScissorsAction(
+                rockpaperscissors::InvalidAction::AlreadyMoved,
+            ),
+        },
+    );
+}
+
+#[test]
+fn test_lobby_query() {
+    golden(
+        "lobby_query",
+        &LobbyQuery {
+            game_type: Some(GameType::RockPaperScissors),
+            status: Some(LobbyStatus::Open),
+            name: Some(String::from("b")),
+            offset: Some(1),
+            limit: Some(2),
+        },
+    );
+    golden("lobby_query_default", &LobbyQuery::default());
+}
+
+#[test]
+fn test_duplicate_tokens_are_rejected() {
+    let mut json = serde_json::to_value(Game::TicTacToe(tictactoe_in_play())).unwrap();
+    json["tokens"][P2] = Value::from("x");
+
+    assert!(serde_json::from_value::<Game>(json).is_err());
+}
+
+#[test]
+fn test_protocol_mismatch() {
+    assert!(common::protocol_incompatibility(VersionInfo::current().protocol).is_none());
+    assert!(common::protocol_incompatibility(PROTOCOL_VERSION + 1)
+        .unwrap()
+        .contains("newer"));
+    assert!(common::protocol_incompatibility(0)
+        .unwrap()
+        .contains("older"));
+}
+
+#[test]
+fn test_schemas() {
+    schema::<Action>("action");
+    schema::<Game>("game");
+    schema::<Lobby>("lobby");
+    schema::<VersionedGame>("versioned_game");
+    schema::<JoinResponse>("join_response");
+    schema::<CreateLobbyRequest>("create_lobby_request");
+    schema::<CreateLobbyResponse>("create_lobby_response");
+    schema::<ServerMessage>("server_message");
+    schema::<VersionInfo>("version_info");
+    schema::<ErrorResponse>("error_response");
+}
@@ -0,0 +1,165 @@
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::{Outcome, State};
+use rocket_contrib::json::Json;
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use common::{BotCredentials, JoinResponse, Lobby, RegisterBotRequest, TurnEvent};
+
+use crate::error::ApiResult;
+use crate::AppState;
+
+/// How long `/bots/events` waits for something to happen when no timeout is given.
+const DEFAULT_EVENT_TIMEOUT: u64 = 30;
+const MAX_EVENT_TIMEOUT: u64 = 60;
+
+/// A player a bot holds in some lobby.
+#[derive(Clone, Debug)]
+pub struct Seat {
+    pub lobby: String,
+    pub player: Uuid,
+}
+
+/// A bot registered through the API, keyed by its API key.
+#[derive(Clone, Debug)]
+pub struct RegisteredBot {
+    pub id: Uuid,
+    pub name: String,
+    pub seats: Vec<Seat>,
+}
+
+/// A request made with a valid `X-Api-Key` header.
+pub struct AuthenticatedBot {
+    pub api_key: String,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for AuthenticatedBot {
+    type Error = &'static str;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let api_key = match request.headers().get_one("X-Api-Key") {
+            Some(api_key) => api_key,
+            None => return Outcome::Failure((Status::Unauthorized, "missing api key")),
+        };
+
+        let state = match request.guard::<State<AppState>>() {
+            Outcome::Success(state) => state,
+            _ => return Outcome::Failure((Status::InternalServerError, "missing app state")),
+        };
+
+        let bots = state.registered_bots.lock();
+
+        match bots.get(api_key) {
+            Some(_) => Outcome::Success(AuthenticatedBot {
+                api_key: api_key.to_owned(),
+            }),
+            None => Outcome::Failure((Status::Unauthorized, "invalid api key")),
+        }
+    }
+}
+
+/// Register a bot, getting the API key it authenticates with
+#[post("/bots/register", data = "<request>")]
+pub fn register_bot(
+    request: Json<RegisterBotRequest>,
+    state: State<AppState>,
+) -> Json<BotCredentials> {
+    let credentials = BotCredentials {
+        bot: Uuid::new_v4(),
+        api_key: Uuid::new_v4().to_simple().to_string(),
+    };
+
+    state.registered_bots.lock().insert(
+        credentials.api_key.clone(),
+        RegisteredBot {
+            id: credentials.bot,
+            name: request.0.name,
+            seats: vec![],
+        },
+    );
+
+    Json(credentials)
+}
+
+/// Join a lobby as a bot, so its turns show up in `/bots/events`
+#[post("/bots/lobbies/<lobby>/join")]
+pub fn bot_join(
+    bot: AuthenticatedBot,
+    lobby: String,
+    state: State<AppState>,
+) -> ApiResult<JoinResponse> {
+    // `join_game` takes the request state, so hold on to it for recording the seat afterwards.
+    let app = state.inner();
+
+    let join = crate::join_game(lobby.clone(), state)?;
+
+    if let Some(registered) = app.registered_bots.lock().get_mut(&bot.api_key) {
+        registered.seats.push(Seat {
+            lobby,
+            player: join.player,
+        });
+    }
+
+    Ok(join)
+}
+
+fn turn_events(lobbies: &HashMap<String, Lobby>, seats: &[Seat]) -> Vec<TurnEvent> {
+    seats
+        .iter()
+        .filter_map(|seat| {
+            let lobby = lobbies.get(&seat.lobby)?;
+
+            if lobby.game.legal_actions(seat.player).is_empty() {
+                None
+            } else {
+                Some(TurnEvent {
+                    lobby: seat.lobby.clone(),
+                    player: seat.player,
+                    game: lobby.game.clone(),
+                    version: lobby.version,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Wait until it's the bot's turn in any of its lobbies
+///
+/// Returns every lobby waiting on the bot, or an empty list once `timeout` seconds pass.
+#[get("/bots/events?<timeout>")]
+pub fn bot_events(
+    bot: AuthenticatedBot,
+    timeout: Option<u64>,
+    state: State<AppState>,
+) -> ApiResult<Vec<TurnEvent>> {
+    let seats = match state.registered_bots.lock().get(&bot.api_key) {
+        Some(registered) => registered.seats.clone(),
+        None => vec![],
+    };
+
+    let timeout = timeout
+        .unwrap_or(DEFAULT_EVENT_TIMEOUT)
+        .min(MAX_EVENT_TIMEOUT);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+
+    let mut lobbies = state.lobbies.lock();
+
+    loop {
+        let events = turn_events(&lobbies, &seats);
+
+        if !events.is_empty() {
+            return Ok(Json(events));
+        }
+
+        if state
+            .changes
+            .wait_for_bot(&bot.api_key, &mut lobbies, deadline)?
+        {
+            return Ok(Json(vec![]));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use uuid::Uuid;
+
+use common::error::ApiError;
+use common::rockpaperscissors;
+use common::rockpaperscissors_strategy::{FrequencyPredictor, Strategy};
+use common::tictactoe::{self, BoardCell};
+use common::tictactoe_solver::Solver;
+use common::{Action, BotDifficulty, Game};
+
+/// A server-side player which fills an empty seat in a lobby.
+#[derive(Clone, Debug)]
+pub struct Bot {
+    pub player: Uuid,
+    pub difficulty: BotDifficulty,
+}
+
+impl Bot {
+    pub fn new(difficulty: BotDifficulty) -> Self {
+        Self {
+            player: Uuid::new_v4(),
+            difficulty,
+        }
+    }
+
+    /// The action this bot wants to take next, or `None` if the game isn't waiting on it.
+    ///
+    /// `bots` are the players in the same lobby that are bots, including this one.
+    pub fn choose_action(&self, game: &Game, bots: &[Uuid], solver: &mut Solver) -> Option<Action> {
+        match game {
+            Game::TicTacToe(state) => self.choose_tictactoe(state, solver).map(Action::TicTacToe),
+            Game::RockPaperScissors(state) => self
+                .choose_rockpaperscissors(state, bots)
+                .map(Action::RockPaperScissors),
+        }
+    }
+
+    fn choose_tictactoe(
+        &self,
+        state: &tictactoe::GameState,
+        solver: &mut Solver,
+    ) -> Option<tictactoe::PlayerAction> {
+        let (board, tokens) = match state {
+            tictactoe::GameState::WaitingForInput {
+                active_player,
+                board,
+                tokens,
+                ..
+            } if *active_player == self.player => (board, tokens),
+            _ => return None,
+        };
+
+        let token: BoardCell = *tokens.get_by_left(&self.player)?;
+
+        let mut rng = rand::thread_rng();
+
+        let perfect = match self.difficulty {
+            BotDifficulty::Easy => false,
+            BotDifficulty::Medium => rng.gen_bool(0.5),
+            BotDifficulty::Hard => true,
+        };
+
+        let position = if perfect {
+            *solver.analyse(board, token).best_moves.choose(&mut rng)?
+        } else {
+            let actions = tictactoe::legal_actions(state, self.player);
+
+            match actions.choose(&mut rng)? {
+                tictactoe::PlayerAction::PlaceToken { position, .. } => *position,
+                _ => return None,
+            }
+        };
+
+        Some(tictactoe::PlayerAction::PlaceToken {
+            player: self.player,
+            position,
+        })
+    }
+
+    fn choose_rockpaperscissors(
+        &self,
+        state: &rockpaperscissors::GameState,
+        bots: &[Uuid],
+    ) -> Option<rockpaperscissors::PlayerAction> {
+        let (players, input, history) = match state {
+            rockpaperscissors::GameState::WaitingForInput {
+                players,
+                input,
+                history,
+                ..
+            } => (players, input, history),
+            _ => return None,
+        };
+
+        if state.legal_actions(self.player).is_empty() {
+            return None;
+        }
+
+        // A move made first is visible in the game state, so only go first against other bots.
+        if input.is_none() && !players.iter().all(|player| bots.contains(player)) {
+            return None;
+        }
+
+        let strategy = match self.difficulty {
+            BotDifficulty::Easy => Strategy::new(vec![]),
+            BotDifficulty::Medium => Strategy::new(vec![Box::new(FrequencyPredictor::new())]),
+            BotDifficulty::Hard => Strategy::ensemble(),
+        };
+
+        let action = strategy
+            .from_history(history, self.player)
+            .choose(&mut rand::thread_rng());
+
+        Some(rockpaperscissors::PlayerAction::Move {
+            player: self.player,
+            action,
+        })
+    }
+}
+
+/// Let the bots in a lobby play until the game is waiting on a human.
+pub fn play(
+    game: &mut Game,
+    bots: &[Bot],
+    solver: &mut Solver,
+    apply: impl Fn(&Game, Action) -> Result<Game, ApiError>,
+) {
+    let ids: Vec<Uuid> = bots.iter().map(|bot| bot.player).collect();
+
+    loop {
+        let mut acted = false;
+
+        for bot in bots {
+            if let Some(action) = bot.choose_action(game, &ids, solver) {
+                match apply(game, action) {
+                    Ok(new_state) => {
+                        *game = new_state;
+                        acted = true;
+                    }
+                    Err(e) => {
+                        // Bots only pick from the legal actions, so this is a bug in the bot. Stop
+                        // rather than ask it again for the same move.
+                        debug_assert!(false, "bot {} made an invalid move: {}", bot.player, e);
+                        return;
+                    }
+                }
+            }
+        }
+
+        if !acted {
+            break;
+        }
+    }
+}
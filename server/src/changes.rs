@@ -0,0 +1,119 @@
+//! Waking long polls when what they're waiting on changes.
+//!
+//! Every waiter holds one of Rocket's sync workers until it's woken or times out, so only so many
+//! are let in at once and the rest are turned away with [`ApiError::Busy`]. The cap is half the
+//! configured workers (`ROCKET_WORKERS`, twice the CPU cores by default), which leaves the other
+//! half for ordinary requests.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use common::error::ApiError;
+use common::Lobby;
+
+/// What waiters wait with, the lock on every lobby.
+pub type LobbiesGuard<'a> = MutexGuard<'a, HashMap<String, Lobby>>;
+
+pub struct Changes {
+    /// Notified when the game in the lobby with that ID changes.
+    lobbies: Mutex<HashMap<String, Arc<Condvar>>>,
+
+    /// Notified when a game changes in any lobby the bot with that API key is seated in.
+    bots: Mutex<HashMap<String, Arc<Condvar>>>,
+
+    waiting: AtomicUsize,
+    max_waiting: usize,
+}
+
+/// Gives its place back when the wait is over.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Changes {
+    /// Let at most `max_waiting` requests wait at once.
+    pub fn new(max_waiting: usize) -> Self {
+        Self {
+            lobbies: Mutex::new(HashMap::new()),
+            bots: Mutex::new(HashMap::new()),
+            waiting: AtomicUsize::new(0),
+            max_waiting,
+        }
+    }
+
+    /// Release `lobbies` until the game in `lobby` changes or `deadline` passes. Returns whether
+    /// it timed out.
+    pub fn wait_for_lobby(
+        &self,
+        lobby: &str,
+        lobbies: &mut LobbiesGuard,
+        deadline: Instant,
+    ) -> Result<bool, ApiError> {
+        self.wait(&self.lobbies, lobby, lobbies, deadline)
+    }
+
+    /// Release `lobbies` until a game changes where the bot with `api_key` is seated, or
+    /// `deadline` passes. Returns whether it timed out.
+    pub fn wait_for_bot(
+        &self,
+        api_key: &str,
+        lobbies: &mut LobbiesGuard,
+        deadline: Instant,
+    ) -> Result<bool, ApiError> {
+        self.wait(&self.bots, api_key, lobbies, deadline)
+    }
+
+    /// Wake whoever is waiting on `lobby`. Call with the lobbies locked.
+    pub fn lobby_changed(&self, lobby: &str) {
+        notify(&self.lobbies, lobby);
+    }
+
+    /// Wake the bot with `api_key` if it's waiting. Call with the lobbies locked.
+    pub fn bot_changed(&self, api_key: &str) {
+        notify(&self.bots, api_key);
+    }
+
+    fn wait(
+        &self,
+        condvars: &Mutex<HashMap<String, Arc<Condvar>>>,
+        key: &str,
+        lobbies: &mut LobbiesGuard,
+        deadline: Instant,
+    ) -> Result<bool, ApiError> {
+        if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.max_waiting {
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(ApiError::Busy);
+        }
+
+        let _waiting = Waiting(&self.waiting);
+
+        let condvar = condvars
+            .lock()
+            .entry(key.to_owned())
+            .or_insert_with(|| Arc::new(Condvar::new()))
+            .clone();
+
+        let timed_out = condvar.wait_until(lobbies, deadline).timed_out();
+
+        // Nobody else is waiting on it, and nobody can start to while the lobbies are locked.
+        if Arc::strong_count(&condvar) == 2 {
+            condvars.lock().remove(key);
+        }
+
+        Ok(timed_out)
+    }
+}
+
+fn notify(condvars: &Mutex<HashMap<String, Arc<Condvar>>>, key: &str) {
+    if let Some(condvar) = condvars.lock().get(key) {
+        condvar.notify_all();
+    }
+}
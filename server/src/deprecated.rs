@@ -0,0 +1,35 @@
+//! The API used to be served from `/`. Those paths still work, but are deprecated in favour of
+//! the same routes under `API_BASE`.
+
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::response::Response;
+use rocket::Request;
+
+use crate::API_BASE;
+
+/// Marks responses to the old unversioned paths with `Deprecation` and a `Link` to their
+/// replacement.
+pub struct UnversionedPaths;
+
+impl Fairing for UnversionedPaths {
+    fn info(&self) -> Info {
+        Info {
+            name: "Deprecated unversioned paths",
+            kind: Kind::Response,
+        }
+    }
+
+    fn on_response(&self, request: &Request, response: &mut Response) {
+        let path = request.uri().path();
+
+        if request.route().is_none() || path.starts_with(API_BASE) {
+            return;
+        }
+
+        response.set_raw_header("Deprecation", "true");
+        response.set_raw_header(
+            "Link",
+            format!("<{}{}>; rel=\"successor-version\"", API_BASE, path),
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use rocket::http::Status;
+use rocket::request::Request;
+use rocket::response::{self, Responder, Response};
+use rocket_contrib::json::Json;
+
+use common::error::{ApiError, ErrorResponse};
+
+/// Responds with an [`ApiError`] as `{"error": ...}` and the status it maps to.
+#[derive(Debug)]
+pub struct Error(pub ApiError);
+
+pub type ApiResult<T> = Result<Json<T>, Error>;
+
+impl From<ApiError> for Error {
+    fn from(error: ApiError) -> Self {
+        Error(error)
+    }
+}
+
+impl<'r> Responder<'r> for Error {
+    fn respond_to(self, request: &Request) -> response::Result<'r> {
+        let status = Status::from_code(self.0.status()).unwrap_or(Status::InternalServerError);
+
+        Response::build_from(Json(ErrorResponse { error: self.0 }).respond_to(request)?)
+            .status(status)
+            .ok()
+    }
+}
+
+#[catch(400)]
+pub fn bad_request() -> Error {
+    Error(ApiError::BadRequest(String::from("malformed request")))
+}
+
+/// Rejected by the `X-Api-Key` guard on the bot endpoints.
+#[catch(401)]
+pub fn unauthorized() -> Error {
+    Error(ApiError::Unauthorized)
+}
+
+#[catch(404)]
+pub fn not_found() -> Error {
+    Error(ApiError::NotFound)
+}
+
+/// A JSON body that parsed but didn't match the expected type.
+#[catch(422)]
+pub fn unprocessable_entity() -> Error {
+    Error(ApiError::InvalidBody)
+}
@@ -4,166 +4,406 @@
 #[macro_use]
 extern crate rocket;
 
-use rocket::State;
+mod bot_api;
+mod bots;
+mod changes;
+mod deprecated;
+mod error;
+mod openapi;
+mod websocket;
+
+use rocket::{Route, State};
 use rocket_contrib::json::{Json, JsonValue};
 
-use serde_json::json;
+use serde::de::DeserializeOwned;
 
 use uuid::Uuid;
 
+use common::error::ApiError;
+use common::tictactoe_solver::{Analysis, Solver};
+use common::AddBotRequest;
+use common::AnalysisRequest;
 use common::CreateLobbyRequest;
+use common::CreateLobbyResponse;
 use common::Game;
 use common::Lobby;
+use common::LobbyQuery;
+use common::LobbyStatus;
+use common::ServerMessage;
+use common::VersionInfo;
+use common::VersionedGame;
 
 use parking_lot::Mutex;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use common::Action;
 use common::GameType;
 use common::JoinResponse;
 use common::{rockpaperscissors, tictactoe};
 
-struct AppState {
-    lobbies: Mutex<HashMap<String, Lobby>>,
+use bot_api::RegisteredBot;
+use bots::Bot;
+use changes::Changes;
+use error::ApiResult;
+use websocket::Hub;
+
+/// Where the current version of the API is mounted.
+const API_BASE: &str = "/api/v1";
+
+/// How long `/lobbies/<lobby>/state?since=` waits for a change when no timeout is given.
+const DEFAULT_STATE_TIMEOUT: u64 = 30;
+const MAX_STATE_TIMEOUT: u64 = 60;
+
+/// How long a lobby can go without a move before it counts as abandoned.
+const ABANDONED_AFTER: Duration = Duration::from_secs(10 * 60);
+
+pub struct AppState {
+    /// Shared with the WebSocket server, which sends each new watcher the current game.
+    lobbies: websocket::Lobbies,
+
+    /// Wakes long polls when the games they're waiting on change.
+    changes: Changes,
+
+    /// When each lobby's game last changed, for noticing abandoned ones.
+    active: Mutex<HashMap<String, Instant>>,
+
+    /// Clients watching lobbies over WebSockets.
+    hub: Arc<Hub>,
+
+    solver: Mutex<Solver>,
+
+    /// The bots seated in each lobby.
+    bots: Mutex<HashMap<String, Vec<Bot>>>,
+
+    /// Bots playing through the API, keyed by API key.
+    registered_bots: Mutex<HashMap<String, RegisteredBot>>,
+}
+
+fn join_action(game_type: &GameType, player: Uuid) -> Action {
+    match game_type {
+        GameType::TicTacToe => Action::TicTacToe(tictactoe::PlayerAction::Join { player }),
+        GameType::RockPaperScissors => {
+            Action::RockPaperScissors(rockpaperscissors::PlayerAction::Join { player })
+        }
+    }
+}
+
+fn apply_action(game: &Game, action: Action) -> Result<Game, ApiError> {
+    match game.clone() {
+        Game::TicTacToe(state) => {
+            if let Action::TicTacToe(action) = action {
+                match common::tictactoe::process_input(action, state) {
+                    Ok(new_state) => Ok(Game::TicTacToe(new_state)),
+                    Err(e) => Err(ApiError::InvalidTicTacToeAction(e)),
+                }
+            } else {
+                Err(ApiError::WrongGameType)
+            }
+        }
+        Game::RockPaperScissors(state) => {
+            if let Action::RockPaperScissors(action) = action {
+                match state.apply(action) {
+                    Ok(new_state) => Ok(Game::RockPaperScissors(new_state)),
+                    Err(e) => Err(ApiError::InvalidRockPaperScissorsAction(e)),
+                }
+            } else {
+                Err(ApiError::WrongGameType)
+            }
+        }
+    }
+}
+
+/// Mark `lobby` abandoned if it hasn't finished but nobody has played in it for a while.
+fn check_abandoned(lobby: &mut Lobby, active: &HashMap<String, Instant>) {
+    if let Some(active) = active.get(&lobby.id) {
+        lobby.abandon_if_idle(active.elapsed(), ABANDONED_AFTER);
+    }
+}
+
+/// Parse a query parameter the way it's written in JSON, minus the quotes, e.g. `tic_tac_toe`.
+fn query_value<T: DeserializeOwned>(
+    name: &str,
+    value: Option<String>,
+) -> Result<Option<T>, ApiError> {
+    match value {
+        Some(value) => serde_json::from_value(serde_json::Value::String(value.clone()))
+            .map(Some)
+            .map_err(|_| ApiError::BadRequest(format!("invalid {}: {:?}", name, value))),
+        None => Ok(None),
+    }
 }
 
-#[get("/lobbies")]
-fn list_games(state: State<AppState>) -> Json<HashMap<String, Lobby>> {
-    Json(state.lobbies.lock().clone())
+/// List the lobbies in order of name, narrowed down by the parts of a `LobbyQuery` given
+#[get("/lobbies?<game_type>&<status>&<name>&<offset>&<limit>")]
+fn list_games(
+    game_type: Option<String>,
+    status: Option<String>,
+    name: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    state: State<AppState>,
+) -> ApiResult<Vec<Lobby>> {
+    let query = LobbyQuery {
+        game_type: query_value("game_type", game_type)?,
+        status: query_value("status", status)?,
+        name,
+        offset,
+        limit,
+    };
+
+    let mut lobbies = state.lobbies.lock();
+    let active = state.active.lock();
+
+    for lobby in lobbies.values_mut() {
+        check_abandoned(lobby, &active);
+    }
+
+    Ok(Json(
+        query.apply(lobbies.values()).into_iter().cloned().collect(),
+    ))
 }
 
 /// Join the game, get a player identifier UUID
 #[post("/lobbies/<lobby>/join")]
-fn join_game(lobby: String, state: State<AppState>) -> JsonValue {
+fn join_game(lobby: String, state: State<AppState>) -> ApiResult<JoinResponse> {
     let player = Uuid::new_v4();
 
-    let lobby: Option<Lobby> = {
+    // `perform_action` takes the request state, so hold on to it for announcing the join.
+    let app = state.inner();
+
+    let lobby: Lobby = {
         let lock = state.lobbies.lock();
-        lock.get(&lobby).map(|x| x.clone())
+        lock.get(&lobby).cloned().ok_or(ApiError::LobbyNotFound)?
     };
 
-    let val = if let Some(lobby) = lobby.clone() {
-        match lobby.game_type {
-            GameType::TicTacToe => perform_action(
-                lobby.name.clone(),
-                Json(Action::TicTacToe(tictactoe::PlayerAction::Join { player })),
-                state,
-            ),
-            GameType::RockPaperScissors => perform_action(
-                lobby.name.clone(),
-                Json(Action::RockPaperScissors(
-                    rockpaperscissors::PlayerAction::Join { player },
-                )),
-                state,
-            ),
-        }
-    } else {
-        return JsonValue(json!({
-            "error": "lobby not found"
-        }));
-    };
+    perform_action(
+        lobby.id.clone(),
+        Json(join_action(&lobby.game_type, player)),
+        state,
+    )?;
 
-    if val.get("error").is_some() {
-        return val;
+    if let Some(joined) = app.lobbies.lock().get(&lobby.id) {
+        app.hub
+            .publish(&joined.id, &ServerMessage::Lobby(joined.clone()));
     }
 
-    JsonValue(
-        serde_json::to_value(JoinResponse {
-            player,
-            game_type: lobby.unwrap().game_type,
-        })
-        .unwrap(),
-    )
+    Ok(Json(JoinResponse {
+        player,
+        game_type: lobby.game_type,
+    }))
 }
 
-/// Join the game, get a player identifier UUID
+/// Apply an action to the lobby's game, returning the new state
 #[post("/lobbies/<lobby>/action", data = "<body>")]
-fn perform_action(lobby: String, body: Json<Action>, state: State<AppState>) -> JsonValue {
-    let res = match state.lobbies.lock().get_mut(&lobby) {
+fn perform_action(lobby: String, body: Json<Action>, state: State<AppState>) -> ApiResult<Game> {
+    match state.lobbies.lock().get_mut(&lobby) {
         Some(lobby) => {
-            let new_state = match lobby.game.clone() {
-                Game::TicTacToe(state) => {
-                    if let Action::TicTacToe(action) = body.0 {
-                        match common::tictactoe::process_input(action, state) {
-                            Ok(new_state) => Game::TicTacToe(new_state),
-                            Err(e) => {
-                                return JsonValue(json!({
-                                    "error": serde_json::to_value(e).unwrap()
-                                }))
-                            }
-                        }
-                    } else {
-                        return JsonValue(json!({
-                            "error": "invalid game type"
-                        }));
-                    }
-                }
-                Game::RockPaperScissors(state) => {
-                    println!("{:?}", body);
-
-                    if let Action::RockPaperScissors(action) = body.0 {
-                        match state.apply(action) {
-                            Ok(new_state) => Game::RockPaperScissors(new_state),
-                            Err(e) => {
-                                println!("err: {:?}", e);
-                                return JsonValue(json!({
-                                    "error": serde_json::to_value(e).unwrap()
-                                }));
-                            }
-                        }
-                    } else {
-                        println!("err: invalid game type");
-                        return JsonValue(json!({
-                            "error": "invalid game type"
-                        }));
-                    }
+            let mut active = state.active.lock();
+
+            check_abandoned(lobby, &active);
+            lobby.admit(&body.0)?;
+
+            let joined = body.0.is_join();
+            lobby.game = apply_action(&lobby.game, body.0)?;
+
+            if let Some(bots) = state.bots.lock().get(&lobby.id) {
+                bots::play(
+                    &mut lobby.game,
+                    bots,
+                    &mut state.solver.lock(),
+                    apply_action,
+                );
+            }
+
+            lobby.changed(joined);
+            active.insert(lobby.id.clone(), Instant::now());
+
+            state.changes.lobby_changed(&lobby.id);
+            for (api_key, bot) in state.registered_bots.lock().iter() {
+                if bot.seats.iter().any(|seat| seat.lobby == lobby.id) {
+                    state.changes.bot_changed(api_key);
                 }
-            };
+            }
 
-            lobby.game = new_state;
+            state
+                .hub
+                .publish(&lobby.id, &ServerMessage::State(lobby.game.clone()));
 
-            serde_json::to_value(lobby.game.clone()).unwrap()
+            Ok(Json(lobby.game.clone()))
         }
-        None => json!({
-            "error": "lobby not found"
-        }),
-    };
-
-    JsonValue(res)
+        None => Err(ApiError::LobbyNotFound.into()),
+    }
 }
 
-/// Get the status of the game
-///
+/// Get the status of the game along with its version
 ///
-#[get("/lobbies/<lobby>/state")]
-fn get_state(lobby: String, state: State<AppState>) -> JsonValue {
-    let res: serde_json::Value = match state.lobbies.lock().get(&lobby) {
-        Some(lobby) => serde_json::to_value(lobby.game.clone()).unwrap(),
-        None => json!({
-            "error": "lobby not found"
-        }),
-    };
+/// With `since`, waits until the lobby's version is past it or `timeout` seconds pass.
+#[get("/lobbies/<lobby>/state?<since>&<timeout>")]
+fn get_state(
+    lobby: String,
+    since: Option<u64>,
+    timeout: Option<u64>,
+    state: State<AppState>,
+) -> ApiResult<VersionedGame> {
+    let timeout = timeout
+        .unwrap_or(DEFAULT_STATE_TIMEOUT)
+        .min(MAX_STATE_TIMEOUT);
+    let deadline = Instant::now() + Duration::from_secs(timeout);
 
-    JsonValue(res)
+    let mut lobbies = state.lobbies.lock();
+
+    loop {
+        let current = lobbies.get(&lobby).ok_or(ApiError::LobbyNotFound)?;
+
+        let waiting = since.map_or(false, |since| current.version <= since);
+        if !waiting || Instant::now() >= deadline {
+            return Ok(Json(VersionedGame {
+                version: current.version,
+                game: current.game.clone(),
+            }));
+        }
+
+        state
+            .changes
+            .wait_for_lobby(&lobby, &mut lobbies, deadline)?;
+    }
 }
 
-/// Get the status of the game
-///
+/// List the actions a player can currently take
+#[get("/lobbies/<lobby>/legal-actions?<player>")]
+fn legal_actions(lobby: String, player: String, state: State<AppState>) -> ApiResult<Vec<Action>> {
+    let player = Uuid::parse_str(&player)
+        .map_err(|_| ApiError::BadRequest(String::from("invalid player id")))?;
+
+    match state.lobbies.lock().get(&lobby) {
+        Some(lobby) => Ok(Json(lobby.game.legal_actions(player))),
+        None => Err(ApiError::LobbyNotFound.into()),
+    }
+}
+
+/// Create a lobby, getting its ID
 ///
+/// Fails if a lobby that's open or in progress already has the name, ignoring case.
 #[post("/lobbies", data = "<lobby>")]
-fn create_lobby(lobby: Json<CreateLobbyRequest>, state: State<AppState>) -> JsonValue {
-    state.lobbies.lock().insert(
-        lobby.0.name.clone(),
-        Lobby {
-            name: lobby.0.name.clone(),
-            players: 0,
-            max_players: 2,
-            game: Game::from(lobby.0.game.clone()),
-            game_type: lobby.0.game.clone(),
-        },
-    );
+fn create_lobby(
+    lobby: Json<CreateLobbyRequest>,
+    state: State<AppState>,
+) -> ApiResult<CreateLobbyResponse> {
+    let request = lobby.0;
+
+    if request.name.trim().is_empty() {
+        return Err(ApiError::BadRequest(String::from("the lobby needs a name")).into());
+    }
+
+    let id = Uuid::new_v4().to_string();
+
+    {
+        let mut lobbies = state.lobbies.lock();
+        let mut active = state.active.lock();
+
+        let taken = lobbies.values_mut().any(|lobby| {
+            check_abandoned(lobby, &active);
+            lobby.holds_name(&request.name)
+        });
+
+        if taken {
+            return Err(ApiError::LobbyNameTaken.into());
+        }
+
+        lobbies.insert(
+            id.clone(),
+            Lobby {
+                id: id.clone(),
+                name: request.name,
+                players: 0,
+                max_players: 2,
+                game: Game::from(request.game.clone()),
+                game_type: request.game,
+                version: 0,
+                status: LobbyStatus::Open,
+            },
+        );
+        active.insert(id.clone(), Instant::now());
+    }
+
+    if let Some(difficulty) = request.bot {
+        add_bot(id.clone(), Json(AddBotRequest { difficulty }), state)?;
+    }
 
-    JsonValue(json!({}))
+    Ok(Json(CreateLobbyResponse { id }))
+}
+
+/// Seat a server-side bot in the lobby
+#[post("/lobbies/<lobby>/bots", data = "<request>")]
+fn add_bot(lobby: String, request: Json<AddBotRequest>, state: State<AppState>) -> ApiResult<Game> {
+    let game_type = match state.lobbies.lock().get(&lobby) {
+        Some(lobby) => lobby.game_type.clone(),
+        None => return Err(ApiError::LobbyNotFound.into()),
+    };
+
+    let bot = Bot::new(request.0.difficulty);
+    let player = bot.player;
+
+    // `perform_action` takes the request state, so hold on to it for cleaning up afterwards.
+    let app = state.inner();
+
+    app.bots
+        .lock()
+        .entry(lobby.clone())
+        .or_insert_with(Vec::new)
+        .push(bot);
+
+    let res = perform_action(lobby.clone(), Json(join_action(&game_type, player)), state);
+
+    if res.is_err() {
+        if let Some(bots) = app.bots.lock().get_mut(&lobby) {
+            bots.retain(|bot| bot.player != player);
+        }
+    }
+
+    res
+}
+
+/// Solve a tic-tac-toe position, giving its value and best moves for the side to move
+#[post("/analysis/tictactoe", data = "<request>")]
+fn analyse_tictactoe(request: Json<AnalysisRequest>, state: State<AppState>) -> Json<Analysis> {
+    Json(
+        state
+            .solver
+            .lock()
+            .analyse(&request.0.board, request.0.to_move),
+    )
+}
+
+/// Report the protocol version and what this server supports, for clients to check first
+#[get("/version")]
+fn version() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
+
+/// Describe the API as an OpenAPI document
+#[get("/openapi.json")]
+fn openapi() -> JsonValue {
+    JsonValue(openapi::document())
+}
+
+fn api_routes() -> Vec<Route> {
+    routes![
+        version,
+        list_games,
+        join_game,
+        get_state,
+        legal_actions,
+        perform_action,
+        create_lobby,
+        add_bot,
+        analyse_tictactoe,
+        bot_api::register_bot,
+        bot_api::bot_join,
+        bot_api::bot_events
+    ]
 }
 
 fn main() {
@@ -180,19 +420,38 @@ fn main() {
         },
     );*/
 
-    rocket::ignite()
+    let lobbies = Arc::new(Mutex::new(map));
+    let hub = Arc::new(Hub::default());
+
+    websocket::listen(
+        format!("0.0.0.0:{}", common::WEBSOCKET_PORT),
+        lobbies.clone(),
+        hub.clone(),
+    );
+
+    let rocket = rocket::ignite();
+    // See `changes` for why.
+    let max_waiting = usize::from(rocket.config().workers / 2).max(1);
+
+    rocket
         .manage(AppState {
-            lobbies: Mutex::new(map),
+            lobbies,
+            changes: Changes::new(max_waiting),
+            active: Mutex::new(HashMap::new()),
+            hub,
+            solver: Mutex::new(Solver::new()),
+            bots: Mutex::new(HashMap::new()),
+            registered_bots: Mutex::new(HashMap::new()),
         })
-        .mount(
-            "/",
-            routes![
-                list_games,
-                join_game,
-                get_state,
-                perform_action,
-                create_lobby
-            ],
-        )
+        .register(catchers![
+            error::bad_request,
+            error::unauthorized,
+            error::not_found,
+            error::unprocessable_entity
+        ])
+        .attach(deprecated::UnversionedPaths)
+        .mount(API_BASE, api_routes())
+        .mount(API_BASE, routes![openapi])
+        .mount("/", api_routes())
         .launch();
 }
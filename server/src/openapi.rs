@@ -0,0 +1,289 @@
+//! Describes the API as an OpenAPI 3 document, served at `/api/v1/openapi.json`.
+//!
+//! Request and response schemas come from the `common` types, so they can't drift from what the
+//! routes actually accept. The paths are the routes mounted in `main`, each with a description
+//! from [`operation`].
+
+use rocket::http::Method;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use common::error::ErrorResponse;
+use common::tictactoe_solver::Analysis;
+use common::{
+    Action, AddBotRequest, AnalysisRequest, BotCredentials, CreateLobbyRequest,
+    CreateLobbyResponse, Game, GameType, JoinResponse, Lobby, LobbyStatus, RegisterBotRequest,
+    TurnEvent, VersionInfo, VersionedGame,
+};
+
+use crate::{api_routes, API_BASE};
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap()
+}
+
+fn path_parameter(name: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string" },
+    })
+}
+
+fn query_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema,
+    })
+}
+
+struct Operation {
+    summary: &'static str,
+    parameters: Vec<Value>,
+    request: Option<Value>,
+    response: Value,
+
+    /// Needs the `X-Api-Key` header of a registered bot.
+    bot_only: bool,
+}
+
+impl Operation {
+    fn new(summary: &'static str, response: Value) -> Self {
+        Self {
+            summary,
+            parameters: vec![],
+            request: None,
+            response,
+            bot_only: false,
+        }
+    }
+
+    fn parameter(mut self, parameter: Value) -> Self {
+        self.parameters.push(parameter);
+        self
+    }
+
+    fn request(mut self, request: Value) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    fn bot_only(mut self) -> Self {
+        self.bot_only = true;
+        self
+    }
+
+    fn into_value(self) -> Value {
+        let mut operation = json!({
+            "summary": self.summary,
+            "parameters": self.parameters,
+            "responses": {
+                "200": {
+                    "description": "Success",
+                    "content": { "application/json": { "schema": self.response } },
+                },
+                "default": {
+                    "description": "Failure, with a status matching the error code",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/ErrorResponse" },
+                        },
+                    },
+                },
+            },
+        });
+
+        if let Some(request) = self.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": request } },
+            });
+        }
+
+        if self.bot_only {
+            operation["security"] = json!([{ "apiKey": [] }]);
+        }
+
+        operation
+    }
+}
+
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    gen.subschema_for::<ErrorResponse>();
+
+    let mut paths = Map::new();
+
+    for route in api_routes() {
+        let path = openapi_path(route.uri.path());
+
+        if let Some(operation) = operation(route.method, &path, &mut gen) {
+            let methods = paths.entry(path).or_insert_with(|| json!({}));
+            methods[method_name(route.method)] = operation.into_value();
+        }
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Game server",
+            "version": "1",
+        },
+        "servers": [{ "url": API_BASE }],
+        "paths": paths,
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+            },
+        },
+    })
+}
+
+/// A route's path as OpenAPI writes it, e.g. `/lobbies/{lobby}/join` for `/lobbies/<lobby>/join`.
+fn openapi_path(path: &str) -> String {
+    path.replace('<', "{").replace('>', "}")
+}
+
+fn method_name(method: Method) -> String {
+    method.as_str().to_lowercase()
+}
+
+/// What the route at `method` and `path` does, `None` if it isn't described yet.
+fn operation(method: Method, path: &str, gen: &mut SchemaGenerator) -> Option<Operation> {
+    let lobby = || path_parameter("lobby");
+    let timeout = || {
+        query_parameter(
+            "timeout",
+            "Seconds to wait before giving up, 30 by default and at most 60",
+            json!({ "type": "integer", "minimum": 0 }),
+        )
+    };
+
+    let operation = match (method, path) {
+        (Method::Get, "/version") => Operation::new(
+            "Get the protocol version, game types and features this server supports",
+            schema::<VersionInfo>(gen),
+        ),
+        (Method::Get, "/lobbies") => Operation::new(
+            "List the lobbies in order of name, ignoring case",
+            schema::<Vec<Lobby>>(gen),
+        )
+        .parameter(query_parameter(
+            "game_type",
+            "Only lobbies for this game",
+            schema::<GameType>(gen),
+        ))
+        .parameter(query_parameter(
+            "status",
+            "Only lobbies where the game is at this point",
+            schema::<LobbyStatus>(gen),
+        ))
+        .parameter(query_parameter(
+            "name",
+            "Only lobbies with names starting with this, ignoring case",
+            json!({ "type": "string" }),
+        ))
+        .parameter(query_parameter(
+            "offset",
+            "Skip this many of the matching lobbies, in the order they're listed in",
+            json!({ "type": "integer", "minimum": 0 }),
+        ))
+        .parameter(query_parameter(
+            "limit",
+            "List at most this many lobbies",
+            json!({ "type": "integer", "minimum": 0 }),
+        )),
+        (Method::Post, "/lobbies") => Operation::new(
+            "Create a lobby under a name no open lobby has, getting its ID",
+            schema::<CreateLobbyResponse>(gen),
+        )
+        .request(schema::<CreateLobbyRequest>(gen)),
+        (Method::Post, "/lobbies/{lobby}/join") => Operation::new(
+            "Join the game, getting a player ID to act as",
+            schema::<JoinResponse>(gen),
+        )
+        .parameter(lobby()),
+        (Method::Post, "/lobbies/{lobby}/action") => Operation::new(
+            "Apply an action to the lobby's game, returning the new state",
+            schema::<Game>(gen),
+        )
+        .parameter(lobby())
+        .request(schema::<Action>(gen)),
+        (Method::Get, "/lobbies/{lobby}/state") => Operation::new(
+            "Get the game and its version, or with `since`, wait for it to change past that",
+            schema::<VersionedGame>(gen),
+        )
+        .parameter(lobby())
+        .parameter(query_parameter(
+            "since",
+            "A version from an earlier response to wait past",
+            json!({ "type": "integer", "minimum": 0 }),
+        ))
+        .parameter(timeout()),
+        (Method::Get, "/lobbies/{lobby}/legal-actions") => Operation::new(
+            "List the actions a player can currently take",
+            schema::<Vec<Action>>(gen),
+        )
+        .parameter(lobby())
+        .parameter(json!({
+            "name": "player",
+            "in": "query",
+            "required": true,
+            "schema": { "type": "string", "format": "uuid" },
+        })),
+        (Method::Post, "/lobbies/{lobby}/bots") => {
+            Operation::new("Seat a server-side bot in the lobby", schema::<Game>(gen))
+                .parameter(lobby())
+                .request(schema::<AddBotRequest>(gen))
+        }
+        (Method::Post, "/analysis/tictactoe") => Operation::new(
+            "Solve a tic-tac-toe position for the side to move",
+            schema::<Analysis>(gen),
+        )
+        .request(schema::<AnalysisRequest>(gen)),
+        (Method::Post, "/bots/register") => Operation::new(
+            "Register a bot, getting the API key it authenticates with",
+            schema::<BotCredentials>(gen),
+        )
+        .request(schema::<RegisterBotRequest>(gen)),
+        (Method::Post, "/bots/lobbies/{lobby}/join") => Operation::new(
+            "Join a lobby as a bot, so its turns show up in /bots/events",
+            schema::<JoinResponse>(gen),
+        )
+        .parameter(lobby())
+        .bot_only(),
+        (Method::Get, "/bots/events") => Operation::new(
+            "Wait until it's the bot's turn in any of its lobbies",
+            schema::<Vec<TurnEvent>>(gen),
+        )
+        .parameter(timeout())
+        .bot_only(),
+        _ => return None,
+    };
+
+    Some(operation)
+}
+
+#[test]
+fn test_every_route_is_described() {
+    let document = document();
+
+    for route in api_routes() {
+        let path = openapi_path(route.uri.path());
+        let method = method_name(route.method);
+
+        assert!(
+            document["paths"][path.as_str()][method.as_str()].is_object(),
+            "{} {} is missing from the OpenAPI document",
+            method,
+            path
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Pushes game updates to clients watching a lobby, so they don't have to poll for its state.
+//!
+//! Clients connect to `ws://<host>:WEBSOCKET_PORT/lobbies/<lobby>` and receive a
+//! [`ServerMessage`] as JSON text whenever something happens in that lobby.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+
+use parking_lot::Mutex;
+use rocket::http::RawStr;
+use ws::{CloseCode, Handler, Handshake, Sender};
+
+use common::{Lobby, ServerMessage};
+
+pub type Lobbies = Arc<Mutex<HashMap<String, Lobby>>>;
+
+/// The open connections watching each lobby.
+#[derive(Default)]
+pub struct Hub {
+    watchers: Mutex<HashMap<String, Vec<Sender>>>,
+}
+
+impl Hub {
+    /// Send `message` to everyone watching `lobby`, forgetting connections that have gone away.
+    pub fn publish(&self, lobby: &str, message: &ServerMessage) {
+        let text = text(message);
+
+        if let Some(watchers) = self.watchers.lock().get_mut(lobby) {
+            watchers.retain(|out| out.send(text.as_str()).is_ok());
+        }
+    }
+
+    fn watch(&self, lobby: &str, out: Sender) {
+        self.watchers
+            .lock()
+            .entry(lobby.to_owned())
+            .or_default()
+            .push(out);
+    }
+
+    fn unwatch(&self, lobby: &str, out: &Sender) {
+        if let Some(watchers) = self.watchers.lock().get_mut(lobby) {
+            watchers.retain(|watcher| watcher.token() != out.token());
+        }
+    }
+}
+
+struct Watcher {
+    out: Sender,
+    hub: Arc<Hub>,
+    lobbies: Lobbies,
+    lobby: Option<String>,
+}
+
+fn text(message: &ServerMessage) -> String {
+    serde_json::to_string(message).unwrap()
+}
+
+/// The lobby ID in a `/lobbies/<lobby>` resource, percent-decoded like Rocket does for the HTTP
+/// API's paths. Any query string is ignored.
+fn lobby_id(resource: &str) -> Option<String> {
+    let path = resource.split('?').next().unwrap_or(resource);
+    let lobby = path.strip_prefix("/lobbies/")?;
+
+    RawStr::from_str(lobby)
+        .percent_decode()
+        .ok()
+        .map(|lobby| lobby.into_owned())
+}
+
+impl Handler for Watcher {
+    fn on_open(&mut self, shake: Handshake) -> ws::Result<()> {
+        let lobby = match lobby_id(shake.request.resource()) {
+            Some(lobby) => lobby,
+            None => {
+                self.out.send(text(&ServerMessage::Error(String::from(
+                    "unknown endpoint",
+                ))))?;
+                return self.out.close(CloseCode::Policy);
+            }
+        };
+
+        // Hold the lobbies while subscribing, so no update can slip in after the first state.
+        let lobbies = self.lobbies.lock();
+
+        match lobbies.get(&lobby) {
+            Some(current) => {
+                self.out
+                    .send(text(&ServerMessage::State(current.game.clone())))?;
+                self.hub.watch(&lobby, self.out.clone());
+                self.lobby = Some(lobby);
+                Ok(())
+            }
+            None => {
+                self.out
+                    .send(text(&ServerMessage::Error(String::from("lobby not found"))))?;
+                self.out.close(CloseCode::Policy)
+            }
+        }
+    }
+
+    fn on_close(&mut self, _code: CloseCode, _reason: &str) {
+        if let Some(lobby) = &self.lobby {
+            self.hub.unwatch(lobby, &self.out);
+        }
+    }
+}
+
+/// Accept WebSocket connections on `address` in the background.
+pub fn listen(address: String, lobbies: Lobbies, hub: Arc<Hub>) {
+    thread::spawn(move || {
+        let result = ws::listen(address, |out| Watcher {
+            out,
+            hub: hub.clone(),
+            lobbies: lobbies.clone(),
+            lobby: None,
+        });
+
+        if let Err(e) = result {
+            println!("websocket server stopped: {}", e);
+        }
+    });
+}
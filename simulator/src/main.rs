@@ -0,0 +1,173 @@
+//! Plays many headless games between two strategies directly on the `common` game engines and
+//! reports how they did.
+//!
+//! `simulator <tictactoe|rockpaperscissors> <strategy a> <strategy b> [games]`
+
+mod stats;
+mod strategies;
+
+use std::process;
+use std::time::Instant;
+
+use uuid::Uuid;
+
+use common::rockpaperscissors;
+use common::tictactoe;
+
+use stats::Stats;
+use strategies::{
+    RockPaperScissorsStrategy, TicTacToeStrategy, ROCKPAPERSCISSORS_STRATEGIES,
+    TICTACTOE_STRATEGIES,
+};
+
+const DEFAULT_GAMES: usize = 10000;
+
+fn usage() -> ! {
+    eprintln!("usage: simulator <game> <strategy a> <strategy b> [games]");
+    eprintln!();
+    eprintln!(
+        "  tictactoe strategies: {}",
+        TICTACTOE_STRATEGIES.join(", ")
+    );
+    eprintln!(
+        "  rockpaperscissors strategies: {}",
+        ROCKPAPERSCISSORS_STRATEGIES.join(", ")
+    );
+    process::exit(1);
+}
+
+fn simulate_tictactoe(a: &str, b: &str, games: usize) -> Stats {
+    let mut strategy_a = TicTacToeStrategy::from_name(a).unwrap_or_else(|| usage());
+    let mut strategy_b = TicTacToeStrategy::from_name(b).unwrap_or_else(|| usage());
+
+    let mut rng = rand::thread_rng();
+    let mut stats = Stats::default();
+
+    for _ in 0..games {
+        let player_a = Uuid::new_v4();
+        let player_b = Uuid::new_v4();
+
+        let mut state = tictactoe::GameState::default();
+        for player in &[player_a, player_b] {
+            let join = tictactoe::PlayerAction::Join { player: *player };
+            state = tictactoe::process_input(join, state).unwrap();
+        }
+
+        let mut first = None;
+
+        loop {
+            match state {
+                tictactoe::GameState::WaitingForInput {
+                    active_player,
+                    ref tokens,
+                    board,
+                    ..
+                } => {
+                    let token = *tokens.get_by_left(&active_player).unwrap();
+                    first = first.or(Some(active_player));
+
+                    let start = Instant::now();
+                    let position = if active_player == player_a {
+                        let position = strategy_a.choose(&board, token, &mut rng);
+                        stats.a_timing.record(start.elapsed());
+                        position
+                    } else {
+                        let position = strategy_b.choose(&board, token, &mut rng);
+                        stats.b_timing.record(start.elapsed());
+                        position
+                    };
+
+                    let action = tictactoe::PlayerAction::PlaceToken {
+                        player: active_player,
+                        position,
+                    };
+
+                    state = tictactoe::process_input(action, state).unwrap();
+                    stats.total_length += 1;
+                }
+                tictactoe::GameState::GameOver { winner, .. } => {
+                    stats.record_game(winner, player_a, first);
+                    break;
+                }
+                tictactoe::GameState::WaitingForPlayers { .. } => unreachable!(),
+            }
+        }
+    }
+
+    stats
+}
+
+fn simulate_rockpaperscissors(a: &str, b: &str, games: usize) -> Stats {
+    let mut strategy_a = RockPaperScissorsStrategy::from_name(a).unwrap_or_else(|| usage());
+    let mut strategy_b = RockPaperScissorsStrategy::from_name(b).unwrap_or_else(|| usage());
+
+    let mut rng = rand::thread_rng();
+    let mut stats = Stats::default();
+
+    for _ in 0..games {
+        let player_a = Uuid::new_v4();
+        let player_b = Uuid::new_v4();
+
+        let mut state = rockpaperscissors::GameState::default();
+        for player in &[player_a, player_b] {
+            let join = rockpaperscissors::PlayerAction::Join { player: *player };
+            state = state.apply(join).unwrap();
+        }
+
+        loop {
+            match state {
+                rockpaperscissors::GameState::WaitingForInput { .. } => {
+                    let start = Instant::now();
+                    let move_a = strategy_a.choose(&mut rng);
+                    stats.a_timing.record(start.elapsed());
+
+                    let start = Instant::now();
+                    let move_b = strategy_b.choose(&mut rng);
+                    stats.b_timing.record(start.elapsed());
+
+                    for (player, action) in &[(player_a, move_a), (player_b, move_b)] {
+                        let action = rockpaperscissors::PlayerAction::Move {
+                            player: *player,
+                            action: *action,
+                        };
+                        state = state.apply(action).unwrap();
+                    }
+
+                    strategy_a.observe(move_a, move_b);
+                    strategy_b.observe(move_b, move_a);
+                    stats.total_length += 1;
+                }
+                rockpaperscissors::GameState::GameOver { winner, .. } => {
+                    stats.record_game(Some(winner), player_a, None);
+                    break;
+                }
+                rockpaperscissors::GameState::WaitingForPlayers { .. } => unreachable!(),
+            }
+        }
+    }
+
+    stats
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.len() < 3 {
+        usage();
+    }
+
+    let games = match args.get(3) {
+        Some(games) => games.parse().unwrap_or_else(|_| usage()),
+        None => DEFAULT_GAMES,
+    };
+
+    let (a, b) = (&args[1], &args[2]);
+
+    let stats = match args[0].as_str() {
+        "tictactoe" => simulate_tictactoe(a, b, games),
+        "rockpaperscissors" => simulate_rockpaperscissors(a, b, games),
+        _ => usage(),
+    };
+
+    stats.print(a, b);
+}
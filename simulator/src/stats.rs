@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use uuid::Uuid;
+
+#[derive(Default)]
+pub struct Timing {
+    total: Duration,
+    count: u32,
+    max: Duration,
+}
+
+impl Timing {
+    pub fn record(&mut self, elapsed: Duration) {
+        self.total += elapsed;
+        self.count += 1;
+        self.max = self.max.max(elapsed);
+    }
+
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            Duration::default()
+        } else {
+            self.total / self.count
+        }
+    }
+}
+
+/// Results of many games between two strategies, `a` and `b`.
+#[derive(Default)]
+pub struct Stats {
+    pub games: usize,
+    pub a_wins: usize,
+    pub b_wins: usize,
+    pub draws: usize,
+
+    /// Moves per game for tic-tac-toe, rounds per game for rock-paper-scissors.
+    pub total_length: usize,
+
+    /// Wins, draws and losses for whoever moved first, in games with a turn order.
+    pub first_mover: Option<(usize, usize, usize)>,
+
+    pub a_timing: Timing,
+    pub b_timing: Timing,
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * count as f64 / total as f64
+    }
+}
+
+impl Stats {
+    /// Count a finished game between `a` and another player, `winner` being `None` for a draw.
+    /// `first` is whoever moved first, in games with a turn order.
+    pub fn record_game(&mut self, winner: Option<Uuid>, a: Uuid, first: Option<Uuid>) {
+        self.games += 1;
+
+        match winner {
+            Some(winner) if winner == a => self.a_wins += 1,
+            Some(_) => self.b_wins += 1,
+            None => self.draws += 1,
+        }
+
+        if let Some(first) = first {
+            let first_mover = self.first_mover.get_or_insert((0, 0, 0));
+
+            match winner {
+                Some(winner) if winner == first => first_mover.0 += 1,
+                Some(_) => first_mover.2 += 1,
+                None => first_mover.1 += 1,
+            }
+        }
+    }
+
+    /// What percentage of the games `count` is.
+    pub fn rate(&self, count: usize) -> f64 {
+        percent(count, self.games)
+    }
+
+    pub fn average_length(&self) -> f64 {
+        self.total_length as f64 / self.games.max(1) as f64
+    }
+
+    pub fn print(&self, a: &str, b: &str) {
+        println!("{} games, {} (a) vs {} (b)", self.games, a, b);
+        println!();
+        println!(
+            "  a wins  {:>8} {:>6.1}%",
+            self.a_wins,
+            self.rate(self.a_wins)
+        );
+        println!(
+            "  b wins  {:>8} {:>6.1}%",
+            self.b_wins,
+            self.rate(self.b_wins)
+        );
+        println!(
+            "  draws   {:>8} {:>6.1}%",
+            self.draws,
+            self.rate(self.draws)
+        );
+        println!();
+        println!("  average game length: {:.2}", self.average_length());
+
+        match self.first_mover {
+            Some((wins, draws, losses)) => println!(
+                "  first mover: {:.1}% wins, {:.1}% draws, {:.1}% losses",
+                self.rate(wins),
+                self.rate(draws),
+                self.rate(losses)
+            ),
+            None => println!("  first mover: n/a, moves are simultaneous"),
+        }
+
+        println!();
+        println!(
+            "  a per move: mean {:?}, max {:?}",
+            self.a_timing.mean(),
+            self.a_timing.max
+        );
+        println!(
+            "  b per move: mean {:?}, max {:?}",
+            self.b_timing.mean(),
+            self.b_timing.max
+        );
+    }
+}
+
+#[test]
+fn test_record_game() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut stats = Stats::default();
+
+    stats.record_game(Some(a), a, Some(a));
+    stats.record_game(Some(a), a, Some(b));
+    stats.record_game(Some(b), a, Some(b));
+    stats.record_game(None, a, Some(a));
+
+    assert_eq!(stats.games, 4);
+    assert_eq!((stats.a_wins, stats.b_wins, stats.draws), (2, 1, 1));
+    assert_eq!(stats.first_mover, Some((2, 1, 1)));
+}
+
+#[test]
+fn test_simultaneous_moves() {
+    let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut stats = Stats::default();
+
+    stats.record_game(Some(b), a, None);
+
+    assert_eq!((stats.a_wins, stats.b_wins, stats.draws), (0, 1, 0));
+    assert_eq!(stats.first_mover, None);
+}
+
+#[test]
+fn test_rates() {
+    let a = Uuid::new_v4();
+    let mut stats = Stats::default();
+
+    assert_eq!(stats.rate(0), 0.0);
+    assert_eq!(stats.average_length(), 0.0);
+
+    for _ in 0..3 {
+        stats.record_game(Some(a), a, None);
+    }
+    stats.record_game(None, a, None);
+    stats.total_length = 30;
+
+    assert_eq!(stats.rate(stats.a_wins), 75.0);
+    assert_eq!(stats.rate(stats.draws), 25.0);
+    assert_eq!(stats.average_length(), 7.5);
+}
+
+#[test]
+fn test_timing() {
+    let mut timing = Timing::default();
+    assert_eq!(timing.mean(), Duration::default());
+
+    timing.record(Duration::from_millis(1));
+    timing.record(Duration::from_millis(5));
+
+    assert_eq!(timing.mean(), Duration::from_millis(3));
+    assert_eq!(timing.max, Duration::from_millis(5));
+}
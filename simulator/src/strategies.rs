@@ -0,0 +1,118 @@
+use rand::rngs::ThreadRng;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use common::rockpaperscissors::Move;
+use common::rockpaperscissors_strategy::{FrequencyPredictor, Round, Strategy};
+use common::tictactoe::{Board, BoardCell};
+use common::tictactoe_solver::{empty_cells, Solver};
+
+pub const TICTACTOE_STRATEGIES: [&str; 3] = ["random", "medium", "perfect"];
+
+pub const ROCKPAPERSCISSORS_STRATEGIES: [&str; 6] =
+    ["random", "rock", "cycle", "biased", "frequency", "ensemble"];
+
+pub enum TicTacToeStrategy {
+    Random,
+
+    /// Plays perfectly half of the time and randomly otherwise.
+    Medium(Solver),
+
+    Perfect(Solver),
+}
+
+impl TicTacToeStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(TicTacToeStrategy::Random),
+            "medium" => Some(TicTacToeStrategy::Medium(Solver::new())),
+            "perfect" => Some(TicTacToeStrategy::Perfect(Solver::new())),
+            _ => None,
+        }
+    }
+
+    pub fn choose(
+        &mut self,
+        board: &Board,
+        token: BoardCell,
+        rng: &mut ThreadRng,
+    ) -> (usize, usize) {
+        let solver = match self {
+            TicTacToeStrategy::Random => None,
+            TicTacToeStrategy::Medium(solver) => {
+                if rng.gen_bool(0.5) {
+                    Some(solver)
+                } else {
+                    None
+                }
+            }
+            TicTacToeStrategy::Perfect(solver) => Some(solver),
+        };
+
+        match solver {
+            Some(solver) => *solver.analyse(board, token).best_moves.choose(rng).unwrap(),
+            None => *empty_cells(board).choose(rng).unwrap(),
+        }
+    }
+}
+
+pub enum RockPaperScissorsStrategy {
+    Random,
+    Rock,
+
+    /// Rock, paper, scissors, rock, ...
+    Cycle(usize),
+
+    /// Rock 60% of the time, paper 30% and scissors 10%.
+    Biased,
+
+    /// An adaptive strategy which remembers the last 200 rounds it has played, across games.
+    Adaptive(Strategy),
+}
+
+impl RockPaperScissorsStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(RockPaperScissorsStrategy::Random),
+            "rock" => Some(RockPaperScissorsStrategy::Rock),
+            "cycle" => Some(RockPaperScissorsStrategy::Cycle(0)),
+            "biased" => Some(RockPaperScissorsStrategy::Biased),
+            "frequency" => Some(RockPaperScissorsStrategy::Adaptive(Strategy::new(vec![
+                Box::new(FrequencyPredictor::new()),
+            ]))),
+            "ensemble" => Some(RockPaperScissorsStrategy::Adaptive(Strategy::ensemble())),
+            _ => None,
+        }
+    }
+
+    pub fn choose(&mut self, rng: &mut ThreadRng) -> Move {
+        match self {
+            RockPaperScissorsStrategy::Random => *[Move::Rock, Move::Paper, Move::Scissors]
+                .choose(rng)
+                .unwrap(),
+            RockPaperScissorsStrategy::Rock => Move::Rock,
+            RockPaperScissorsStrategy::Cycle(i) => {
+                *i += 1;
+                [Move::Rock, Move::Paper, Move::Scissors][(*i - 1) % 3]
+            }
+            RockPaperScissorsStrategy::Biased => {
+                let roll: f64 = rng.gen();
+
+                if roll < 0.6 {
+                    Move::Rock
+                } else if roll < 0.9 {
+                    Move::Paper
+                } else {
+                    Move::Scissors
+                }
+            }
+            RockPaperScissorsStrategy::Adaptive(strategy) => strategy.choose(rng),
+        }
+    }
+
+    pub fn observe(&mut self, ours: Move, theirs: Move) {
+        if let RockPaperScissorsStrategy::Adaptive(strategy) = self {
+            strategy.observe(Round { ours, theirs });
+        }
+    }
+}